@@ -2,8 +2,6 @@
 extern crate cookie_factory;
 extern crate test;
 
-use std::iter::repeat;
-
 use test::Bencher;
 
 #[path = "../tests/http/mod.rs"]
@@ -41,9 +39,7 @@ mod tests {
                     value: "Close",
                 },
             ]
-            .iter()
-            .cloned()
-            .collect(),
+            .to_vec(),
             body: b"Hello, world!",
         };
 
@@ -82,13 +78,11 @@ mod macros {
                     value: "Close",
                 },
             ]
-            .iter()
-            .cloned()
-            .collect(),
+            .to_vec(),
             body: b"Hello, world!",
         };
 
-        let mut buffer = repeat(0).take(16384).collect::<Vec<u8>>();
+        let mut buffer = vec![0u8; 16384];
         let index = {
             let (buf, index) = cf_request((&mut buffer[..], 0), &request).unwrap();
 
@@ -133,13 +127,11 @@ mod functions {
                     value: "Close",
                 },
             ]
-            .iter()
-            .cloned()
-            .collect(),
+            .to_vec(),
             body: b"Hello, world!",
         };
 
-        let mut buffer = repeat(0).take(16384).collect::<Vec<u8>>();
+        let mut buffer = vec![0u8; 16384];
         let index = {
             let sr = fn_request(&request);
             let (_, pos) = gen(sr, &mut buffer[..]).unwrap();
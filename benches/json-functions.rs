@@ -31,7 +31,7 @@ pub fn gen_str<'a, 'b: 'a, W: Write>(s: &'b str) -> impl SerializeFn<W> + 'a {
 }
 
 #[inline(always)]
-pub fn gen_bool<'a, W: Write>(b: bool) -> impl SerializeFn<W> {
+pub fn gen_bool<W: Write>(b: bool) -> impl SerializeFn<W> {
     if b {
         string("true")
     } else {
@@ -40,7 +40,7 @@ pub fn gen_bool<'a, W: Write>(b: bool) -> impl SerializeFn<W> {
 }
 
 #[inline(always)]
-pub fn gen_num<'a, W: Write>(_b: f64) -> impl SerializeFn<W> {
+pub fn gen_num<W: Write>(_b: f64) -> impl SerializeFn<W> {
     /*move |out: &'a mut [u8]| {
       let s = format!("{}", b);
       string(s)(out)
@@ -62,7 +62,7 @@ pub fn gen_key_value<'a, 'b: 'a, W: Write>(
     move |out: WriteContext<W>| {
         let out = gen_str(kv.0)(out)?;
         let out = string(":")(out)?;
-        gen_json_value(&kv.1)(out)
+        gen_json_value(kv.1)(out)
     }
 }
 
@@ -87,8 +87,6 @@ pub fn gen_json_value<'a, W: Write>(g: &'a JsonValue) -> impl SerializeFn<W> + '
     }
 }
 
-use std::iter::repeat;
-
 #[test]
 fn json_test() {
     let value = JsonValue::Object(btreemap! {
@@ -101,7 +99,7 @@ fn json_test() {
       }),
     });
 
-    let mut buffer = repeat(0).take(16384).collect::<Vec<u8>>();
+    let mut buffer = vec![0u8; 16384];
     let index = {
         let sr = gen_json_value(&value);
 
@@ -127,9 +125,9 @@ fn functions_json(b: &mut Bencher) {
       }),
     });
 
-    let value = JsonValue::Array(repeat(element).take(10).collect::<Vec<JsonValue>>());
+    let value = JsonValue::Array(vec![element; 10]);
 
-    let mut buffer = repeat(0u8).take(16384).collect::<Vec<_>>();
+    let mut buffer = vec![0u8; 16384];
     let index = {
         let sr = gen_json_value(&value);
 
@@ -146,10 +144,10 @@ fn functions_json(b: &mut Bencher) {
 
 #[bench]
 fn functions_gen_str(b: &mut Bencher) {
-    let mut buffer = repeat(0).take(16384).collect::<Vec<u8>>();
+    let mut buffer = vec![0u8; 16384];
 
     let index = {
-        let sr = gen_str(&"hello");
+        let sr = gen_str("hello");
 
         let (_, res) = gen(sr, &mut buffer[..]).unwrap();
         res as usize
@@ -157,7 +155,7 @@ fn functions_gen_str(b: &mut Bencher) {
 
     b.bytes = index as u64;
     b.iter(|| {
-        let sr = gen_str(&"hello");
+        let sr = gen_str("hello");
         let _ = gen_simple(sr, &mut buffer[..]).unwrap();
     });
 }
@@ -0,0 +1,123 @@
+#![feature(test)]
+extern crate cookie_factory;
+extern crate test;
+
+use cookie_factory::bytes::be_u32;
+use cookie_factory::combinator::reserve_hint;
+use cookie_factory::gen_simple;
+use cookie_factory::multi::{all, all_exact};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use test::Bencher;
+
+const COUNT: usize = 10_000;
+
+/// Allocator counting the reallocations done by the current thread
+struct CountingAlloc;
+
+thread_local! {
+    static REALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        REALLOCS.with(|r| r.set(r.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+fn reallocations<F: FnOnce()>(f: F) -> usize {
+    let before = REALLOCS.with(Cell::get);
+    f();
+    REALLOCS.with(Cell::get) - before
+}
+
+#[test]
+fn multi_vec_reallocations() {
+    let values: Vec<u32> = (0..COUNT as u32).collect();
+
+    let plain = reallocations(|| {
+        gen_simple(all(values.iter().map(|v| be_u32(*v))), Vec::new()).unwrap();
+    });
+    let exact = reallocations(|| {
+        gen_simple(all_exact(values.iter().map(|v| be_u32(*v))), Vec::new()).unwrap();
+    });
+    let hinted = reallocations(|| {
+        let sr = reserve_hint(COUNT * 4, all(values.iter().map(|v| be_u32(*v))));
+        gen_simple(sr, Vec::new()).unwrap();
+    });
+
+    // without the hint, the Vec grows by doubling its capacity, from 8 bytes up to 64k
+    assert!(plain >= 10, "{} reallocations", plain);
+    // all_exact reserves the whole output once the first element is written
+    assert_eq!(exact, 1);
+    assert_eq!(hinted, 0);
+}
+
+#[bench]
+fn multi_all_slice(b: &mut Bencher) {
+    let values: Vec<u32> = (0..COUNT as u32).collect();
+    let mut buffer = vec![0u8; COUNT * 4];
+
+    b.bytes = (COUNT * 4) as u64;
+    b.iter(|| {
+        let sr = all(values.iter().map(|v| be_u32(*v)));
+        let _ = gen_simple(sr, &mut buffer[..]).unwrap();
+    });
+}
+
+#[bench]
+fn multi_all_exact_slice(b: &mut Bencher) {
+    let values: Vec<u32> = (0..COUNT as u32).collect();
+    let mut buffer = vec![0u8; COUNT * 4];
+
+    b.bytes = (COUNT * 4) as u64;
+    b.iter(|| {
+        let sr = all_exact(values.iter().map(|v| be_u32(*v)));
+        let _ = gen_simple(sr, &mut buffer[..]).unwrap();
+    });
+}
+
+#[bench]
+fn multi_all_vec(b: &mut Bencher) {
+    let values: Vec<u32> = (0..COUNT as u32).collect();
+
+    b.bytes = (COUNT * 4) as u64;
+    b.iter(|| {
+        let sr = all(values.iter().map(|v| be_u32(*v)));
+        gen_simple(sr, Vec::new()).unwrap()
+    });
+}
+
+#[bench]
+fn multi_all_exact_vec(b: &mut Bencher) {
+    let values: Vec<u32> = (0..COUNT as u32).collect();
+
+    b.bytes = (COUNT * 4) as u64;
+    b.iter(|| {
+        let sr = all_exact(values.iter().map(|v| be_u32(*v)));
+        gen_simple(sr, Vec::new()).unwrap()
+    });
+}
+
+#[bench]
+fn multi_all_vec_reserve_hint(b: &mut Bencher) {
+    let values: Vec<u32> = (0..COUNT as u32).collect();
+
+    b.bytes = (COUNT * 4) as u64;
+    b.iter(|| {
+        let sr = reserve_hint(COUNT * 4, all(values.iter().map(|v| be_u32(*v))));
+        gen_simple(sr, Vec::new()).unwrap()
+    });
+}
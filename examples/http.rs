@@ -27,9 +27,7 @@ fn main() {
                 value: "Close",
             },
         ]
-        .iter()
-        .cloned()
-        .collect(),
+        .to_vec(),
         body: b"Hello, world!",
     };
 
@@ -1,5 +1,10 @@
+use crate::internal::{
+    gen_simple, BackToTheBuffer, GenError, GenResult, SerializeFn, WriteContext,
+};
 use futures_io::AsyncWrite;
 use futures_util::io::AsyncWriteExt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 pub struct AsyncBufWriter<W: AsyncWrite + Unpin> {
     inner: W,
@@ -57,11 +62,69 @@ impl<W: AsyncWrite + Unpin> std::io::Write for AsyncBufWriter<W> {
     }
 }
 
-pub async fn gen<W: AsyncWrite + Unpin, F: crate::internal::SerializeFn<AsyncBufWriter<W>>>(
+// Serializers can only write to the internal buffer: data is sent to `inner` by `flush`, which
+// cannot be called while serializing, so the reserved region is always still in the buffer.
+impl<W: AsyncWrite + Unpin> BackToTheBuffer for AsyncBufWriter<W> {
+    fn reserve_write_use<
+        Tmp,
+        Gen: Fn(WriteContext<Self>) -> Result<(WriteContext<Self>, Tmp), GenError>,
+        Before: Fn(WriteContext<Self>, Tmp) -> GenResult<Self>,
+    >(
+        s: WriteContext<Self>,
+        reserved: usize,
+        gen: &Gen,
+        before: &Before,
+    ) -> Result<WriteContext<Self>, GenError> {
+        let WriteContext {
+            write: mut w,
+            position: original_position,
+        } = s;
+
+        let start = w.pos;
+        let available = w.buf.len() - start;
+        if available < reserved {
+            return Err(GenError::BufferTooSmall(reserved - available));
+        }
+        w.pos += reserved;
+
+        let (new_context, tmp) = gen(WriteContext {
+            write: w,
+            position: original_position + reserved as u64,
+        })?;
+        let WriteContext {
+            write: mut w,
+            position,
+        } = new_context;
+        let end = w.pos;
+
+        // limit the buffer to the reserved region so `before` cannot overwrite the data
+        let tail = w.buf.split_off(start + reserved);
+        w.pos = start;
+        let tmp_context = before(
+            WriteContext {
+                write: w,
+                position: original_position,
+            },
+            tmp,
+        )?;
+        let mut w = tmp_context.write;
+        w.buf.extend_from_slice(&tail);
+
+        let tmp_written = w.pos - start;
+        if tmp_written != reserved {
+            return Err(GenError::BufferTooBig(reserved - tmp_written));
+        }
+        w.pos = end;
+
+        Ok(WriteContext { write: w, position })
+    }
+}
+
+pub async fn gen<W: AsyncWrite + Unpin, F: SerializeFn<AsyncBufWriter<W>>>(
     f: F,
     w: AsyncBufWriter<W>,
-) -> Result<(AsyncBufWriter<W>, u64), crate::internal::GenError> {
-    match f(crate::internal::WriteContext::from(w)).map(|ctx| ctx.into_inner()) {
+) -> Result<(AsyncBufWriter<W>, u64), GenError> {
+    match f(WriteContext::from(w)).map(|ctx| ctx.into_inner()) {
         Err(e) => Err(e),
         Ok((mut w, _)) => {
             let sz = w.flush().await?;
@@ -69,3 +132,83 @@ pub async fn gen<W: AsyncWrite + Unpin, F: crate::internal::SerializeFn<AsyncBuf
         }
     }
 }
+
+/// Trait for serializers writing directly to an `AsyncWrite`
+///
+/// Unlike `SerializeFn`, an `AsyncSerializeFn` can be suspended in the middle of writing when
+/// the underlying writer is not ready, and resumed when it is polled again. The position of the
+/// `WriteContext` is updated as bytes are accepted by the writer.
+pub trait AsyncSerializeFn<W: AsyncWrite + Unpin> {
+    fn poll_serialize(
+        &mut self,
+        cx: &mut Context<'_>,
+        w: &mut WriteContext<W>,
+    ) -> Poll<Result<(), GenError>>;
+}
+
+/// Adapter running a synchronous serializer, then writing its output to an `AsyncWrite`
+///
+/// The serializer is run into a `Vec<u8>` on the first poll, then the result is written
+/// in as many chunks as the writer requires. Created by `from_sync`.
+pub struct FromSync<F> {
+    f: F,
+    buf: Option<Vec<u8>>,
+    written: usize,
+}
+
+/// Converts a synchronous serializer into an `AsyncSerializeFn`
+pub fn from_sync<F: SerializeFn<Vec<u8>>>(f: F) -> FromSync<F> {
+    FromSync {
+        f,
+        buf: None,
+        written: 0,
+    }
+}
+
+impl<W: AsyncWrite + Unpin, F: SerializeFn<Vec<u8>>> AsyncSerializeFn<W> for FromSync<F> {
+    fn poll_serialize(
+        &mut self,
+        cx: &mut Context<'_>,
+        w: &mut WriteContext<W>,
+    ) -> Poll<Result<(), GenError>> {
+        if self.buf.is_none() {
+            self.buf = Some(gen_simple(&self.f, Vec::new())?);
+        }
+        let buf = self.buf.as_ref().unwrap();
+
+        while self.written < buf.len() {
+            match Pin::new(&mut w.write).poll_write(cx, &buf[self.written..]) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(GenError::IoError(e))),
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(GenError::IoError(
+                        std::io::ErrorKind::WriteZero.into(),
+                    )))
+                }
+                Poll::Ready(Ok(sz)) => {
+                    self.written += sz;
+                    w.position += sz as u64;
+                }
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Runs the given asynchronous serializer `f` with the `AsyncWrite` impl `w`, then flushes `w`
+///
+/// Returns the writer and the number of bytes written.
+pub async fn gen_async<W: AsyncWrite + Unpin, F: AsyncSerializeFn<W>>(
+    mut f: F,
+    w: W,
+) -> Result<(W, u64), GenError> {
+    let mut ctx = WriteContext {
+        write: w,
+        position: 0,
+    };
+    std::future::poll_fn(|cx| f.poll_serialize(cx, &mut ctx)).await?;
+    ctx.write.flush().await?;
+
+    Ok((ctx.write, ctx.position))
+}
@@ -210,6 +210,29 @@ pub fn be_i64<W: Write>(i: i64) -> impl SerializeFn<W> {
     be_u64(i as u64)
 }
 
+/// Writes the bit pattern of an IEEE 754 half-precision float in big endian byte order to the output
+///
+/// The input is the raw 16 bit representation of the `f16` value, not an `f32`: the conversion
+/// to half precision is left to the caller.
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::be_f16};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   // 1.0 in half precision
+///   let (buf, pos) = gen(be_f16(0x3c00u16), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 2);
+///   assert_eq!(buf.len(), 100 - 2);
+/// }
+///
+/// assert_eq!(&buf[..2], &[60u8, 0u8][..]);
+/// ```
+pub fn be_f16<W: Write>(i: u16) -> impl SerializeFn<W> {
+    be_u16(i)
+}
+
 /// Writes an `f32` in big endian byte order to the output
 ///
 /// ```rust
@@ -248,6 +271,35 @@ pub fn be_f64<W: Write>(i: f64) -> impl SerializeFn<W> {
     be_u64(i.to_bits())
 }
 
+/// Writes a fixed-point decimal, `value * 10^scale` rounded to the nearest `i32`, in big endian
+/// byte order to the output
+///
+/// Returns `GenError::InvalidValue` if the scaled value is not finite or does not fit in an `i32`.
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::fixed_point_i32};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(fixed_point_i32(12.34, 2), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &1234i32.to_be_bytes()[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn fixed_point_i32<W: Write>(value: f64, scale: u32) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| {
+        let scaled = (value * 10f64.powi(scale.min(i32::MAX as u32) as i32)).round();
+        if !(scaled >= f64::from(i32::MIN) && scaled <= f64::from(i32::MAX)) {
+            return Err(GenError::InvalidValue);
+        }
+        be_i32(scaled as i32)(out)
+    }
+}
+
 /// Writes an `u8` to the output
 ///
 /// ```rust
@@ -448,6 +500,29 @@ pub fn le_i64<W: Write>(i: i64) -> impl SerializeFn<W> {
     le_u64(i as u64)
 }
 
+/// Writes the bit pattern of an IEEE 754 half-precision float in little endian byte order to the output
+///
+/// The input is the raw 16 bit representation of the `f16` value, not an `f32`: the conversion
+/// to half precision is left to the caller.
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::le_f16};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   // 1.0 in half precision
+///   let (buf, pos) = gen(le_f16(0x3c00u16), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 2);
+///   assert_eq!(buf.len(), 100 - 2);
+/// }
+///
+/// assert_eq!(&buf[..2], &[0u8, 60u8][..]);
+/// ```
+pub fn le_f16<W: Write>(i: u16) -> impl SerializeFn<W> {
+    le_u16(i)
+}
+
 /// Writes an `f32` in little endian byte order to the output
 ///
 /// ```rust
@@ -522,7 +597,7 @@ pub fn ne_u8<W: Write>(i: u8) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..2], &[0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..2], &[1u8, 0u8][..]);
 /// ```
 pub fn ne_u16<W: Write>(i: u16) -> impl SerializeFn<W> {
@@ -546,13 +621,18 @@ pub fn ne_u16<W: Write>(i: u16) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..3], &[0u8, 0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..3], &[1u8, 0u8, 0u8][..]);
 /// ```
 pub fn ne_u24<W: Write>(i: u32) -> impl SerializeFn<W> {
     let len = 3;
+    // the lower 24 bits are the last 3 bytes in big endian, the first 3 in little endian
+    #[cfg(target_endian = "big")]
+    let start = 1;
+    #[cfg(target_endian = "little")]
+    let start = 0;
 
-    move |mut out: WriteContext<W>| try_write!(out, len, &i.to_ne_bytes()[1..])
+    move |mut out: WriteContext<W>| try_write!(out, len, &i.to_ne_bytes()[start..start + len])
 }
 
 /// Writes an `u32` in native byte order to the output
@@ -570,7 +650,7 @@ pub fn ne_u24<W: Write>(i: u32) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..4], &[0u8, 0u8, 0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..4], &[1u8, 0u8, 0u8, 0u8][..]);
 /// ```
 pub fn ne_u32<W: Write>(i: u32) -> impl SerializeFn<W> {
@@ -594,7 +674,7 @@ pub fn ne_u32<W: Write>(i: u32) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..8], &[0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..8], &[1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8][..]);
 /// ```
 pub fn ne_u64<W: Write>(i: u64) -> impl SerializeFn<W> {
@@ -603,6 +683,30 @@ pub fn ne_u64<W: Write>(i: u64) -> impl SerializeFn<W> {
     move |mut out: WriteContext<W>| try_write!(out, len, &i.to_ne_bytes()[..])
 }
 
+/// Writes an `u128` in native byte order to the output
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::ne_u128};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(ne_u128(1u128), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 16);
+///   assert_eq!(buf.len(), 100 - 16);
+/// }
+///
+/// #[cfg(target_endian = "big")]
+/// assert_eq!(&buf[..16], &[0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8][..]);
+/// #[cfg(target_endian = "little")]
+/// assert_eq!(&buf[..16], &[1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8][..]);
+/// ```
+pub fn ne_u128<W: Write>(i: u128) -> impl SerializeFn<W> {
+    let len = 16;
+
+    move |mut out: WriteContext<W>| try_write!(out, len, &i.to_ne_bytes()[..])
+}
+
 /// Writes an `i8` to the output
 ///
 /// ```rust
@@ -637,7 +741,7 @@ pub fn ne_i8<W: Write>(i: i8) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..2], &[0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..2], &[1u8, 0u8][..]);
 /// ```
 pub fn ne_i16<W: Write>(i: i16) -> impl SerializeFn<W> {
@@ -659,7 +763,7 @@ pub fn ne_i16<W: Write>(i: i16) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..3], &[0u8, 0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..3], &[1u8, 0u8, 0u8][..]);
 /// ```
 pub fn ne_i24<W: Write>(i: i32) -> impl SerializeFn<W> {
@@ -681,7 +785,7 @@ pub fn ne_i24<W: Write>(i: i32) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..4], &[0u8, 0u8, 0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..4], &[1u8, 0u8, 0u8, 0u8][..]);
 /// ```
 pub fn ne_i32<W: Write>(i: i32) -> impl SerializeFn<W> {
@@ -703,13 +807,62 @@ pub fn ne_i32<W: Write>(i: i32) -> impl SerializeFn<W> {
 ///
 /// #[cfg(target_endian = "big")]
 /// assert_eq!(&buf[..8], &[0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8][..]);
-/// #[cfg(target_endian = "litte")]
+/// #[cfg(target_endian = "little")]
 /// assert_eq!(&buf[..8], &[1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8][..]);
 /// ```
 pub fn ne_i64<W: Write>(i: i64) -> impl SerializeFn<W> {
     ne_u64(i as u64)
 }
 
+/// Writes an `i128` in native byte order to the output
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::ne_i128};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(ne_i128(-2i128), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 16);
+///   assert_eq!(buf.len(), 100 - 16);
+/// }
+///
+/// #[cfg(target_endian = "big")]
+/// assert_eq!(&buf[15], &0xFEu8);
+/// #[cfg(target_endian = "little")]
+/// assert_eq!(&buf[0], &0xFEu8);
+/// assert!(buf[..16].iter().filter(|b| **b == 0xFF).count() == 15);
+/// ```
+pub fn ne_i128<W: Write>(i: i128) -> impl SerializeFn<W> {
+    ne_u128(i as u128)
+}
+
+/// Writes the bit pattern of an IEEE 754 half-precision float in native byte order to the output
+///
+/// The input is the raw 16 bit representation of the `f16` value, not an `f32`: the conversion
+/// to half precision is left to the caller.
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::ne_f16};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   // 1.0 in half precision
+///   let (buf, pos) = gen(ne_f16(0x3c00u16), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 2);
+///   assert_eq!(buf.len(), 100 - 2);
+/// }
+///
+/// #[cfg(target_endian = "big")]
+/// assert_eq!(&buf[..2], &[60u8, 0u8][..]);
+/// #[cfg(target_endian = "little")]
+/// assert_eq!(&buf[..2], &[0u8, 60u8][..]);
+/// ```
+pub fn ne_f16<W: Write>(i: u16) -> impl SerializeFn<W> {
+    ne_u16(i)
+}
+
 /// Writes an `f32` in native byte order to the output
 ///
 /// ```rust
@@ -753,3 +906,298 @@ pub fn ne_f32<W: Write>(i: f32) -> impl SerializeFn<W> {
 pub fn ne_f64<W: Write>(i: f64) -> impl SerializeFn<W> {
     ne_u64(i.to_bits())
 }
+
+/// Byte order used by the `*_endian` serializers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    /// most significant byte first
+    Big,
+    /// least significant byte first
+    Little,
+}
+
+/// Writes an `u16` in the byte order selected by `endian` to the output
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::{u16_endian, Endianness}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(u16_endian(Endianness::Little, 1u16), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 2);
+///   assert_eq!(buf.len(), 100 - 2);
+/// }
+///
+/// assert_eq!(&buf[..2], &[1u8, 0u8][..]);
+/// ```
+pub fn u16_endian<W: Write>(endian: Endianness, i: u16) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| match endian {
+        Endianness::Big => be_u16(i)(out),
+        Endianness::Little => le_u16(i)(out),
+    }
+}
+
+/// Writes an `u32` in the byte order selected by `endian` to the output
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::{u32_endian, Endianness}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(u32_endian(Endianness::Little, 1u32), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &[1u8, 0u8, 0u8, 0u8][..]);
+/// ```
+pub fn u32_endian<W: Write>(endian: Endianness, i: u32) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| match endian {
+        Endianness::Big => be_u32(i)(out),
+        Endianness::Little => le_u32(i)(out),
+    }
+}
+
+/// Writes an `u64` in the byte order selected by `endian` to the output
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::{u64_endian, Endianness}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(u64_endian(Endianness::Little, 1u64), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &[1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8][..]);
+/// ```
+pub fn u64_endian<W: Write>(endian: Endianness, i: u64) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| match endian {
+        Endianness::Big => be_u64(i)(out),
+        Endianness::Little => le_u64(i)(out),
+    }
+}
+
+/// Writes an `u64` as an unsigned LEB128 varint to the output
+///
+/// Each byte holds 7 bits of the value, least significant group first, with the high bit set on
+/// all bytes but the last. Values below 128 take one byte, and `u64::MAX` takes 10.
+///
+/// ```rust
+/// use cookie_factory::{gen, bytes::leb128_u64};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(leb128_u64(300), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 2);
+///   assert_eq!(buf.len(), 100 - 2);
+/// }
+///
+/// assert_eq!(&buf[..2], &[0xacu8, 0x02][..]);
+/// ```
+pub fn leb128_u64<W: Write>(i: u64) -> impl SerializeFn<W> {
+    move |mut out: WriteContext<W>| {
+        let mut encoded = [0u8; 10];
+        let mut len = 0;
+        let mut value = i;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                encoded[len] = byte;
+                len += 1;
+                break;
+            }
+            encoded[len] = byte | 0x80;
+            len += 1;
+        }
+
+        try_write!(out, len, &encoded[..len])
+    }
+}
+
+/// Writes an `u32` as an MQTT variable byte integer, as used for the remaining length
+///
+/// The encoding is the same as LEB128, but limited to 4 bytes: values above 268435455 return
+/// `GenError::InvalidValue`.
+///
+/// ```rust
+/// use cookie_factory::{gen, GenError, bytes::mqtt_varlen};
+///
+/// let mut buf = [0u8; 4];
+///
+/// let cases: [(u32, &[u8]); 4] = [
+///   (0, &[0x00]),
+///   (127, &[0x7f]),
+///   (128, &[0x80, 0x01]),
+///   (268_435_455, &[0xff, 0xff, 0xff, 0x7f]),
+/// ];
+/// for (value, expected) in cases.iter() {
+///   let (_, pos) = gen(mqtt_varlen(*value), &mut buf[..]).unwrap();
+///   assert_eq!(&buf[..pos as usize], *expected);
+/// }
+///
+/// assert!(matches!(gen(mqtt_varlen(268_435_456), &mut buf[..]), Err(GenError::InvalidValue)));
+/// ```
+pub fn mqtt_varlen<W: Write>(value: u32) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| {
+        if value > 268_435_455 {
+            return Err(GenError::InvalidValue);
+        }
+        leb128_u64(u64::from(value))(out)
+    }
+}
+
+/// Maps a signed integer to an unsigned one with the zigzag encoding, as used by protobuf's
+/// `sint32`
+///
+/// Values of small magnitude are mapped to small values: 0 to 0, -1 to 1, 1 to 2, -2 to 3, etc.
+///
+/// ```rust
+/// use cookie_factory::bytes::zigzag_u32;
+///
+/// assert_eq!(zigzag_u32(-2), 3);
+/// assert_eq!(zigzag_u32(i32::MAX), u32::MAX - 1);
+/// ```
+pub fn zigzag_u32(i: i32) -> u32 {
+    ((i << 1) ^ (i >> 31)) as u32
+}
+
+/// Maps a signed integer to an unsigned one with the zigzag encoding, as used by protobuf's
+/// `sint64`
+///
+/// ```rust
+/// use cookie_factory::bytes::zigzag_u64;
+///
+/// assert_eq!(zigzag_u64(-2), 3);
+/// assert_eq!(zigzag_u64(i64::MAX), u64::MAX - 1);
+/// ```
+pub fn zigzag_u64(i: i64) -> u64 {
+    ((i << 1) ^ (i >> 63)) as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ne_u24() {
+        let mut buf = [0u8; 6];
+
+        {
+            let out = WriteContext::from(&mut buf[..]);
+            let out = ne_u24(1)(out).unwrap();
+            let out = ne_i24(-2)(out).unwrap();
+            assert_eq!(out.position, 6);
+        }
+
+        #[cfg(target_endian = "little")]
+        assert_eq!(&buf, &[1, 0, 0, 0xfe, 0xff, 0xff]);
+        #[cfg(target_endian = "big")]
+        assert_eq!(&buf, &[0, 0, 1, 0xff, 0xff, 0xfe]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_fixed_point_i32() {
+        let cases = [
+            (12.34, 2, 1234i32),
+            (-12.345, 2, -1235),
+            (0.5, 0, 1),
+            (1.0, 9, 1_000_000_000),
+            (-2147483648.0, 0, i32::MIN),
+        ];
+        for &(value, scale, expected) in cases.iter() {
+            let mut buf = [0u8; 4];
+            fixed_point_i32(value, scale)(WriteContext::from(&mut buf[..])).unwrap();
+            assert_eq!(buf, expected.to_be_bytes(), "{} * 10^{}", value, scale);
+        }
+
+        for &(value, scale) in [(3.0, 9), (2147483647.5, 0), (f64::NAN, 0), (1.0, 400)].iter() {
+            let mut buf = [0u8; 4];
+            let res = fixed_point_i32(value, scale)(WriteContext::from(&mut buf[..]));
+            assert!(
+                matches!(res, Err(GenError::InvalidValue)),
+                "{} * 10^{} should not fit",
+                value,
+                scale
+            );
+        }
+    }
+
+    #[test]
+    fn test_endian() {
+        let mut buf = [0u8; 14];
+
+        {
+            let big_endian = |big: bool| {
+                if big {
+                    Endianness::Big
+                } else {
+                    Endianness::Little
+                }
+            };
+            let out = WriteContext::from(&mut buf[..]);
+            let out = u32_endian(big_endian(true), 0x0102_0304)(out).unwrap();
+            let out = u32_endian(big_endian(false), 0x0102_0304)(out).unwrap();
+            let out = u16_endian(big_endian(false), 0x0102)(out).unwrap();
+            let out = u16_endian(big_endian(true), 0x0102)(out).unwrap();
+            let out = u16_endian(big_endian(false), 0x0a0b)(out).unwrap();
+            assert_eq!(out.position, 14);
+        }
+
+        assert_eq!(&buf[..], &[1, 2, 3, 4, 4, 3, 2, 1, 2, 1, 1, 2, 11, 10][..]);
+
+        let mut buf = [0u8; 8];
+        {
+            let out = WriteContext::from(&mut buf[..]);
+            let out = u64_endian(Endianness::Big, 0x0102_0304_0506_0708)(out).unwrap();
+            assert_eq!(out.position, 8);
+        }
+        assert_eq!(&buf[..], &[1, 2, 3, 4, 5, 6, 7, 8][..]);
+    }
+
+    #[test]
+    fn test_leb128_u64() {
+        let cases: [(u64, &[u8]); 5] = [
+            (0, &[0]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (624_485, &[0xe5, 0x8e, 0x26]),
+            (
+                u64::MAX,
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+        ];
+
+        for (value, expected) in cases.iter() {
+            let mut buf = [0u8; 10];
+            let len = {
+                let out = leb128_u64(*value)(WriteContext::from(&mut buf[..])).unwrap();
+                out.position as usize
+            };
+            assert_eq!(&buf[..len], *expected);
+        }
+    }
+
+    #[test]
+    fn test_zigzag_u32() {
+        assert_eq!(zigzag_u32(0), 0);
+        assert_eq!(zigzag_u32(-1), 1);
+        assert_eq!(zigzag_u32(1), 2);
+        assert_eq!(zigzag_u32(i32::MIN), u32::MAX);
+    }
+
+    #[test]
+    fn test_zigzag_u64() {
+        assert_eq!(zigzag_u64(0), 0);
+        assert_eq!(zigzag_u64(-1), 1);
+        assert_eq!(zigzag_u64(1), 2);
+        assert_eq!(zigzag_u64(i64::MIN), u64::MAX);
+    }
+}
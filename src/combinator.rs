@@ -1,6 +1,6 @@
 //! basic serializers
 use crate::internal::*;
-use crate::lib::std::io::Write;
+use crate::lib::std::io::{Seek as _, SeekFrom, Write};
 
 macro_rules! try_write(($out:ident, $len:ident, $data:expr) => (
     match $out.write($data) {
@@ -31,6 +31,212 @@ pub fn slice<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
     move |mut out: WriteContext<W>| try_write!(out, len, data.as_ref())
 }
 
+/// Writes a byte slice to the output, calling `write` until the whole slice is written
+///
+/// Unlike `slice`, a short write is not an error: the rest of the data is written with further
+/// calls. `GenError::BufferTooSmall` is only returned if the output stops accepting data.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::slice_all};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(slice_all(&b"abcd"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abcd"[..]);
+/// ```
+pub fn slice_all<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
+    move |mut out: WriteContext<W>| {
+        let mut data = data.as_ref();
+        while !data.is_empty() {
+            match out.write(data) {
+                Err(io) => return Err(GenError::IoError(io)),
+                Ok(0) => return Err(GenError::BufferTooSmall(data.len())),
+                Ok(n) => data = &data[n..],
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Writes an already encoded fragment to the output
+///
+/// The fragment is treated as opaque and copied verbatim: this is the same as `slice`, but
+/// makes it explicit that pre-serialized data (like a cached header) is spliced in.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::raw_fragment};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(raw_fragment(&b"\x01\x02\x03"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 3);
+///   assert_eq!(buf.len(), 100 - 3);
+/// }
+///
+/// assert_eq!(&buf[..3], &b"\x01\x02\x03"[..]);
+/// ```
+pub fn raw_fragment<'a, W: Write + 'a>(bytes: &'a [u8]) -> impl SerializeFn<W> + 'a {
+    slice(bytes)
+}
+
+/// Writes the length of a byte slice as a big endian `u32`, followed by the slice
+///
+/// Returns `GenError::InvalidValue` if the length does not fit in a `u32`.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::length_prefixed_slice};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(length_prefixed_slice(&b"abc"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 7);
+///   assert_eq!(buf.len(), 100 - 7);
+/// }
+///
+/// assert_eq!(&buf[..7], &b"\x00\x00\x00\x03abc"[..]);
+/// ```
+pub fn length_prefixed_slice<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
+    length_prefixed_slice_with(
+        |len| {
+            move |out: WriteContext<W>| {
+                if len > u32::MAX as usize {
+                    return Err(GenError::InvalidValue);
+                }
+                crate::bytes::be_u32(len as u32)(out)
+            }
+        },
+        data,
+    )
+}
+
+/// Writes the length of a byte slice as a big endian `u8`, followed by the slice
+///
+/// Returns `GenError::InvalidValue` if the length does not fit in a `u8`.
+///
+/// ```rust
+/// use cookie_factory::{gen, GenError, combinator::slice_u8_prefixed};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(slice_u8_prefixed(&b"abc"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"\x03abc"[..]);
+///
+/// let data = vec![0u8; 256];
+/// let mut buf = vec![0u8; 257];
+/// assert!(matches!(gen(slice_u8_prefixed(&data[..]), &mut buf[..]), Err(GenError::InvalidValue)));
+/// ```
+pub fn slice_u8_prefixed<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
+    length_prefixed_slice_with(
+        |len| {
+            move |out: WriteContext<W>| {
+                if len > u8::MAX as usize {
+                    return Err(GenError::InvalidValue);
+                }
+                crate::bytes::be_u8(len as u8)(out)
+            }
+        },
+        data,
+    )
+}
+
+/// Writes the length of a byte slice as a big endian `u16`, followed by the slice
+///
+/// Returns `GenError::InvalidValue` if the length does not fit in a `u16`.
+///
+/// ```rust
+/// use cookie_factory::{gen, GenError, combinator::slice_u16_prefixed};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(slice_u16_prefixed(&b"abc"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 5);
+///   assert_eq!(buf.len(), 100 - 5);
+/// }
+///
+/// assert_eq!(&buf[..5], &b"\x00\x03abc"[..]);
+///
+/// let data = vec![0u8; 65536];
+/// let mut buf = vec![0u8; 65538];
+/// assert!(matches!(gen(slice_u16_prefixed(&data[..]), &mut buf[..]), Err(GenError::InvalidValue)));
+/// ```
+pub fn slice_u16_prefixed<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
+    length_prefixed_slice_with(
+        |len| {
+            move |out: WriteContext<W>| {
+                if len > u16::MAX as usize {
+                    return Err(GenError::InvalidValue);
+                }
+                crate::bytes::be_u16(len as u16)(out)
+            }
+        },
+        data,
+    )
+}
+
+/// Writes the length of a byte slice as a big endian `u32`, followed by the slice
+///
+/// Returns `GenError::InvalidValue` if the length does not fit in a `u32`.
+///
+/// ```rust
+/// use cookie_factory::{gen, GenError, combinator::slice_u32_prefixed};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(slice_u32_prefixed(&b"abc"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 7);
+///   assert_eq!(buf.len(), 100 - 7);
+/// }
+///
+/// assert_eq!(&buf[..7], &b"\x00\x00\x00\x03abc"[..]);
+/// ```
+pub fn slice_u32_prefixed<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
+    length_prefixed_slice(data)
+}
+
+/// Writes the length of a byte slice with the serializer returned by `len_writer`, followed by
+/// the slice
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::length_prefixed_slice_with, bytes::le_u16};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(length_prefixed_slice_with(|len| le_u16(len as u16), &b"abc"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 5);
+///   assert_eq!(buf.len(), 100 - 5);
+/// }
+///
+/// assert_eq!(&buf[..5], &b"\x03\x00abc"[..]);
+/// ```
+pub fn length_prefixed_slice_with<S, L, G, W: Write>(len_writer: L, data: S) -> impl SerializeFn<W>
+where
+    S: AsRef<[u8]>,
+    L: Fn(usize) -> G,
+    G: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| {
+        let data = data.as_ref();
+        let len = data.len();
+        let mut out = len_writer(len)(out)?;
+        try_write!(out, len, data)
+    }
+}
+
 /// Writes a string slice to the output
 ///
 /// ```rust
@@ -52,8 +258,143 @@ pub fn string<S: AsRef<str>, W: Write>(data: S) -> impl SerializeFn<W> {
     move |mut out: WriteContext<W>| try_write!(out, len, data.as_ref().as_bytes())
 }
 
-/// Writes an hex string to the output
-#[cfg(feature = "std")]
+/// Writes already encoded text bytes to the output
+///
+/// This behaves exactly like `slice`, and only exists to make the intent clearer in serializer
+/// chains when the bytes are known to be text, but are not held as a `str`. No UTF-8 validation
+/// is done.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::text};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(text(b"abcd"), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abcd"[..]);
+/// ```
+pub fn text<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
+    slice(data)
+}
+
+/// Writes a string slice in a field of exactly `width` bytes, padded with `pad`
+///
+/// Strings longer than `width` are truncated on a character boundary. Returns
+/// `GenError::InvalidValue` if the first character of the string does not fit in `width`.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::string_fixed};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(string_fixed("abc", 6, b' '), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 6);
+///   assert_eq!(buf.len(), 100 - 6);
+/// }
+///
+/// assert_eq!(&buf[..6], &b"abc   "[..]);
+/// ```
+pub fn string_fixed<S: AsRef<str>, W: Write>(
+    data: S,
+    width: usize,
+    pad: u8,
+) -> impl SerializeFn<W> {
+    move |mut out: WriteContext<W>| {
+        let data = data.as_ref();
+
+        let mut len = crate::lib::std::cmp::min(width, data.len());
+        while !data.is_char_boundary(len) {
+            len -= 1;
+        }
+        if len == 0 && !data.is_empty() {
+            return Err(GenError::InvalidValue);
+        }
+
+        out = try_write!(out, len, &data.as_bytes()[..len])?;
+
+        let padding = [pad; 32];
+        let mut remaining = width - len;
+        while remaining > 0 {
+            let chunk = crate::lib::std::cmp::min(remaining, padding.len());
+            out = try_write!(out, chunk, &padding[..chunk])?;
+            remaining -= chunk;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Writes a string slice followed by a NUL byte to the output
+///
+/// Returns `GenError::InvalidValue` if the string contains a NUL byte.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::cstr};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(cstr("hi"), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 3);
+///   assert_eq!(buf.len(), 100 - 3);
+/// }
+///
+/// assert_eq!(&buf[..3], &[0x68u8, 0x69, 0x00][..]);
+/// ```
+pub fn cstr<S: AsRef<str>, W: Write>(data: S) -> impl SerializeFn<W> {
+    move |mut out: WriteContext<W>| {
+        let data = data.as_ref().as_bytes();
+        if data.contains(&0) {
+            return Err(GenError::InvalidValue);
+        }
+
+        let len = data.len();
+        out = try_write!(out, len, data)?;
+        let len = 1;
+        try_write!(out, len, &[0u8][..])
+    }
+}
+
+/// `fmt::Write` adapter over a `Write`, keeping the I/O error that made formatting fail
+struct FmtWriter<'a, W> {
+    out: &'a mut W,
+    error: Option<crate::lib::std::io::Error>,
+}
+
+impl<W: Write> crate::lib::std::fmt::Write for FmtWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> crate::lib::std::fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            crate::lib::std::fmt::Error
+        })
+    }
+}
+
+/// Formats `args` to the output, also without std
+fn write_fmt<W: Write>(
+    mut out: WriteContext<W>,
+    args: crate::lib::std::fmt::Arguments<'_>,
+) -> GenResult<W> {
+    let mut writer = FmtWriter {
+        out: &mut out,
+        error: None,
+    };
+    match crate::lib::std::fmt::Write::write_fmt(&mut writer, args) {
+        Ok(()) => Ok(out),
+        Err(_) => Err(writer
+            .error
+            .map(GenError::IoError)
+            .unwrap_or(GenError::FormatError)),
+    }
+}
+
+/// Writes an hex string to the output, using the `UpperHex` implementation of `data`
+///
 /// ```rust
 /// use cookie_factory::{gen, combinator::hex};
 ///
@@ -68,118 +409,1905 @@ pub fn string<S: AsRef<str>, W: Write>(data: S) -> impl SerializeFn<W> {
 /// assert_eq!(&buf[..2], &b"2A"[..]);
 /// ```
 pub fn hex<S: crate::lib::std::fmt::UpperHex, W: Write>(data: S) -> impl SerializeFn<W> {
-    move |mut out: WriteContext<W>| match write!(out, "{:X}", data) {
-        Err(io) => Err(GenError::IoError(io)),
-        Ok(()) => Ok(out),
-    }
+    move |out: WriteContext<W>| write_fmt(out, format_args!("{:X}", data))
 }
 
-/// Skips over some input bytes without writing anything
+/// Writes a lowercase hex string to the output, using the `LowerHex` implementation of `data`
 ///
 /// ```rust
-/// use cookie_factory::{gen, combinator::skip};
+/// use cookie_factory::{gen, combinator::hex_lower};
 ///
 /// let mut buf = [0u8; 100];
 ///
-/// let (out, pos) = gen(skip(2), &mut buf[..]).unwrap();
+/// {
+///   let (buf, pos) = gen(hex_lower(0xBEEFu16), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
 ///
-/// assert_eq!(pos, 2);
-/// assert_eq!(out.len(), 98);
+/// assert_eq!(&buf[..4], &b"beef"[..]);
 /// ```
-pub fn skip<W: Write + Skip>(len: usize) -> impl SerializeFn<W> {
-    move |out: WriteContext<W>| W::skip(out, len)
+pub fn hex_lower<S: crate::lib::std::fmt::LowerHex, W: Write>(data: S) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| write_fmt(out, format_args!("{:x}", data))
 }
 
-/// Applies a serializer if the condition is true
+/// Writes a byte slice to the output, replacing the bytes for which `escaper` returns an escape
+/// sequence with that sequence
 ///
 /// ```rust
-/// use cookie_factory::{gen, combinator::{cond, string}};
+/// use cookie_factory::{gen, combinator::escaped};
+///
+/// fn escape_quote(b: u8) -> Option<&'static [u8]> {
+///   match b {
+///     b'"' => Some(&b"\\\""[..]),
+///     _ => None,
+///   }
+/// }
 ///
 /// let mut buf = [0u8; 100];
 ///
 /// {
-///   let (buf, pos) = gen(cond(true, string("abcd")), &mut buf[..]).unwrap();
+///   let (buf, pos) = gen(escaped(&b"a\"b"[..], escape_quote), &mut buf[..]).unwrap();
 ///   assert_eq!(pos, 4);
 ///   assert_eq!(buf.len(), 100 - 4);
 /// }
 ///
-/// assert_eq!(&buf[..4], &b"abcd"[..]);
+/// assert_eq!(&buf[..4], &b"a\\\"b"[..]);
 /// ```
-pub fn cond<F, W: Write>(condition: bool, f: F) -> impl SerializeFn<W>
-where
-    F: SerializeFn<W>,
-{
-    move |out: WriteContext<W>| {
-        if condition {
-            f(out)
-        } else {
-            Ok(out)
+pub fn escaped<S: AsRef<[u8]>, W: Write>(
+    data: S,
+    escaper: fn(u8) -> Option<&'static [u8]>,
+) -> impl SerializeFn<W> {
+    move |mut out: WriteContext<W>| {
+        let data = data.as_ref();
+        let mut start = 0;
+
+        for (i, b) in data.iter().enumerate() {
+            if let Some(escape) = escaper(*b) {
+                let unescaped = &data[start..i];
+                let len = unescaped.len();
+                out = try_write!(out, len, unescaped)?;
+                let len = escape.len();
+                out = try_write!(out, len, escape)?;
+                start = i + 1;
+            }
         }
+
+        let unescaped = &data[start..];
+        let len = unescaped.len();
+        try_write!(out, len, unescaped)
     }
 }
 
-/// Reserves space for the `Before` combinator, applies the `Gen` combinator,
-/// then applies the `Before` combinator with the output from `Gen` onto the
-/// reserved space.
+static JSON_CONTROL_ESCAPES: [&[u8]; 32] = [
+    b"\\u0000", b"\\u0001", b"\\u0002", b"\\u0003", b"\\u0004", b"\\u0005", b"\\u0006", b"\\u0007",
+    b"\\b", b"\\t", b"\\n", b"\\u000b", b"\\f", b"\\r", b"\\u000e", b"\\u000f", b"\\u0010",
+    b"\\u0011", b"\\u0012", b"\\u0013", b"\\u0014", b"\\u0015", b"\\u0016", b"\\u0017", b"\\u0018",
+    b"\\u0019", b"\\u001a", b"\\u001b", b"\\u001c", b"\\u001d", b"\\u001e", b"\\u001f",
+];
+
+fn json_escape(b: u8) -> Option<&'static [u8]> {
+    match b {
+        b'"' => Some(&b"\\\""[..]),
+        b'\\' => Some(&b"\\\\"[..]),
+        0..=0x1f => Some(JSON_CONTROL_ESCAPES[b as usize]),
+        _ => None,
+    }
+}
+
+/// Writes the body of a JSON string, without the surrounding quotes
+///
+/// Quotes, backslashes and control characters are escaped.
 ///
 /// ```rust
-/// use cookie_factory::{gen, gen_simple, sequence::tuple, combinator::{back_to_the_buffer, string}, bytes::be_u8, bytes::be_u32};
+/// use cookie_factory::{gen, combinator::json_string_body};
 ///
-/// let mut buf = [0; 9];
-/// gen_simple(tuple((
-///     back_to_the_buffer(
-///         4,
-///         move |buf| gen(string("test"), buf),
-///         move |buf, len| gen_simple(be_u32(len as u32), buf)
-///     ),
-///     be_u8(42)
-/// )), &mut buf[..]).unwrap();
-/// assert_eq!(&buf, &[0, 0, 0, 4, 't' as u8, 'e' as u8, 's' as u8, 't' as u8, 42]);
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(json_string_body("a\"b\n"), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 6);
+///   assert_eq!(buf.len(), 100 - 6);
+/// }
+///
+/// assert_eq!(&buf[..6], &br#"a\"b\n"#[..]);
 /// ```
-pub fn back_to_the_buffer<W: BackToTheBuffer, Tmp, Gen, Before>(
-    reserved: usize,
-    gen: Gen,
-    before: Before,
-) -> impl SerializeFn<W>
-where
-    Gen: Fn(WriteContext<W>) -> Result<(WriteContext<W>, Tmp), GenError>,
-    Before: Fn(WriteContext<W>, Tmp) -> GenResult<W>,
-{
-    move |w: WriteContext<W>| W::reserve_write_use(w, reserved, &gen, &before)
+pub fn json_string_body<S: AsRef<str>, W: Write>(data: S) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| escaped(data.as_ref().as_bytes(), json_escape)(out)
 }
 
-//missing combinators:
-//or
-//empty
-//then
-//stream
-//length_value
-//text print
-//text upperhex
-//text lowerhex
+/// Writes the `Display` representation of a value, left-padded with `fill` to `width` characters
+///
+/// Values whose representation is wider than `width` are written in full, without truncation.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::display_padded};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(display_padded(42, 5, '0'), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 5);
+///   assert_eq!(buf.len(), 100 - 5);
+/// }
+///
+/// assert_eq!(&buf[..5], &b"00042"[..]);
+///
+/// {
+///   let (buf, pos) = gen(display_padded(1234567, 5, ' '), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 7);
+///   assert_eq!(buf.len(), 100 - 7);
+/// }
+///
+/// assert_eq!(&buf[..7], &b"1234567"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn display_padded<T: crate::lib::std::fmt::Display, W: Write>(
+    value: T,
+    width: usize,
+    fill: char,
+) -> impl SerializeFn<W> {
+    use crate::lib::std::fmt::Write as _;
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::bytes::{be_u32, be_u8};
-    use crate::sequence::tuple;
+    struct CharCounter(usize);
 
-    #[test]
-    fn test_gen_with_length() {
-        let mut buf = [0; 8];
-        {
-            let (len_buf, buf) = buf.split_at_mut(4);
-            let (_, pos) = gen(string("test"), buf).unwrap();
-            gen(be_u32(pos as u32), len_buf).unwrap();
+    impl crate::lib::std::fmt::Write for CharCounter {
+        fn write_str(&mut self, s: &str) -> crate::lib::std::fmt::Result {
+            self.0 += s.chars().count();
+            Ok(())
         }
-        assert_eq!(&buf, &[0, 0, 0, 4, b't', b'e', b's', b't']);
     }
 
-    #[test]
-    fn test_back_to_the_buffer() {
-        let mut buf = [0; 9];
+    move |mut out: WriteContext<W>| {
+        let mut counter = CharCounter(0);
+        // writing to a CharCounter cannot fail
+        let _ = write!(counter, "{}", value);
 
-        let new_buf = gen_simple(
+        let mut fill_buf = [0u8; 4];
+        let fill = fill.encode_utf8(&mut fill_buf).as_bytes();
+        let fill_len = fill.len();
+        for _ in counter.0..width {
+            out = try_write!(out, fill_len, fill)?;
+        }
+
+        match write!(out, "{}", value) {
+            Err(io) => Err(GenError::IoError(io)),
+            Ok(()) => Ok(out),
+        }
+    }
+}
+
+/// Writes the standard base64 encoding of a byte slice to the output, with `=` padding
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::base64};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(base64(&b"a"[..]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+/// assert_eq!(&buf[..4], &b"YQ=="[..]);
+///
+/// gen(base64(&b"ab"[..]), &mut buf[..]).unwrap();
+/// assert_eq!(&buf[..4], &b"YWI="[..]);
+///
+/// gen(base64(&b"abc"[..]), &mut buf[..]).unwrap();
+/// assert_eq!(&buf[..4], &b"YWJj"[..]);
+/// ```
+pub fn base64<S: AsRef<[u8]>, W: Write>(data: S) -> impl SerializeFn<W> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    move |mut out: WriteContext<W>| {
+        for chunk in data.as_ref().chunks(3) {
+            let b0 = chunk[0];
+            let b1 = chunk.get(1).copied().unwrap_or(0);
+            let b2 = chunk.get(2).copied().unwrap_or(0);
+
+            let mut encoded = [
+                ALPHABET[(b0 >> 2) as usize],
+                ALPHABET[(((b0 & 0x03) << 4) | (b1 >> 4)) as usize],
+                ALPHABET[(((b1 & 0x0F) << 2) | (b2 >> 6)) as usize],
+                ALPHABET[(b2 & 0x3F) as usize],
+            ];
+            if chunk.len() < 3 {
+                encoded[3] = b'=';
+            }
+            if chunk.len() < 2 {
+                encoded[2] = b'=';
+            }
+
+            let len = encoded.len();
+            out = try_write!(out, len, &encoded[..])?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Skips over some input bytes without writing anything
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::skip};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let (out, pos) = gen(skip(2), &mut buf[..]).unwrap();
+///
+/// assert_eq!(pos, 2);
+/// assert_eq!(out.len(), 98);
+/// ```
+pub fn skip<W: Write + Skip>(len: usize) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| W::skip(out, len)
+}
+
+/// Writes `len` zero bytes, reserving space to be filled later with `patch_at`
+///
+/// The position of the reserved region can be obtained by recording the position right
+/// before it, e.g. with `record_offset`.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::reserve};
+///
+/// let mut buf = [0xFFu8; 100];
+///
+/// {
+///   let (buf, pos) = gen(reserve(3), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 3);
+///   assert_eq!(buf.len(), 100 - 3);
+/// }
+///
+/// assert_eq!(&buf[..4], &[0u8, 0u8, 0u8, 0xFFu8][..]);
+/// ```
+pub fn reserve<W: Write>(len: usize) -> impl SerializeFn<W> {
+    const ZEROS: [u8; 32] = [0; 32];
+
+    move |mut out: WriteContext<W>| {
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = crate::lib::std::cmp::min(remaining, ZEROS.len());
+            out = try_write!(out, chunk, &ZEROS[..chunk])?;
+            remaining -= chunk;
+        }
+        Ok(out)
+    }
+}
+
+/// Applies `fill` repeatedly until the position is a multiple of `multiple`
+///
+/// Nothing is written if the position is already aligned. Returns `GenError::InvalidValue` if
+/// `multiple` is 0, or if the size of a `fill` unit does not evenly divide the padding needed.
+///
+/// ```rust
+/// use cookie_factory::{gen, sequence::pair, combinator::{pad_with, string}, bytes::be_u8};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(pair(string("abcde"), pad_with(4, be_u8(0xFF))), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &b"abcde\xFF\xFF\xFF"[..]);
+/// ```
+pub fn pad_with<F, W: Write>(multiple: usize, fill: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        if multiple == 0 {
+            return Err(GenError::InvalidValue);
+        }
+        let rem = out.position % multiple as u64;
+        if rem == 0 {
+            return Ok(out);
+        }
+        let target = out.position + (multiple as u64 - rem);
+
+        while out.position < target {
+            let start = out.position;
+            out = fill(out)?;
+            let unit = out.position - start;
+            // `u64::is_multiple_of` needs Rust 1.87
+            #[allow(clippy::manual_is_multiple_of)]
+            if unit == 0 || (target - start) % unit != 0 {
+                return Err(GenError::InvalidValue);
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Moves to the position `offset`, applies the serializer, then goes back to the current position
+///
+/// `offset` is a position in the same coordinates as `WriteContext::position`.
+///
+/// ```rust
+/// use cookie_factory::{gen, sequence::tuple, combinator::{patch_at, reserve, string}, bytes::be_u16};
+/// use cookie_factory::lib::std::io::Cursor;
+///
+/// let mut buf = [0u8; 6];
+///
+/// {
+///   let cursor = Cursor::new(&mut buf[..]);
+///   let (_, pos) = gen(tuple((reserve(2), string("abcd"), patch_at(0, be_u16(4)))), cursor).unwrap();
+///   assert_eq!(pos, 6);
+/// }
+///
+/// assert_eq!(&buf[..], &b"\x00\x04abcd"[..]);
+/// ```
+pub fn patch_at<F, W: Seek>(offset: u64, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        let current = out.position;
+        out.seek(SeekFrom::Current(offset as i64 - current as i64))?;
+        let mut out = f(out)?;
+        let patched = out.position;
+        out.seek(SeekFrom::Current(current as i64 - patched as i64))?;
+        Ok(out)
+    }
+}
+
+/// Seeks the writer to the absolute offset `offset`, applies the serializer, then seeks back
+///
+/// Unlike `patch_at`, `offset` is an offset in the underlying writer, not in the coordinates of
+/// `WriteContext::position`.
+///
+/// ```rust
+/// use cookie_factory::{gen, sequence::tuple, combinator::{reserve, seek_to, string}, bytes::be_u16};
+/// use cookie_factory::lib::std::io::Cursor;
+///
+/// let mut buf = [0u8; 6];
+///
+/// {
+///   let cursor = Cursor::new(&mut buf[..]);
+///   let (cursor, pos) = gen(tuple((reserve(2), string("abcd"), seek_to(0, be_u16(4)))), cursor).unwrap();
+///   assert_eq!(pos, 6);
+///   assert_eq!(cursor.position(), 6);
+/// }
+///
+/// assert_eq!(&buf[..], &b"\x00\x04abcd"[..]);
+/// ```
+pub fn seek_to<F, W: Seek>(offset: u64, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        let current = out.write.stream_position()?;
+        out.seek(SeekFrom::Start(offset))?;
+        let mut out = f(out)?;
+        out.seek(SeekFrom::Start(current))?;
+        Ok(out)
+    }
+}
+
+/// Seeks the writer `delta` bytes from the current offset, applies the serializer, then seeks back
+///
+/// Returns `GenError::InvalidOffset` if the target offset would be negative.
+///
+/// ```rust
+/// use cookie_factory::{gen, sequence::tuple, combinator::{reserve, seek_rel, string}, bytes::be_u16};
+/// use cookie_factory::lib::std::io::Cursor;
+///
+/// let mut buf = [0u8; 6];
+///
+/// {
+///   let cursor = Cursor::new(&mut buf[..]);
+///   let (_, pos) = gen(tuple((string("ab"), reserve(2), string("cd"), seek_rel(-4, be_u16(4)))), cursor).unwrap();
+///   assert_eq!(pos, 6);
+/// }
+///
+/// assert_eq!(&buf[..], &b"ab\x00\x04cd"[..]);
+/// ```
+pub fn seek_rel<F, W: Seek>(delta: i64, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        let current = out.write.stream_position()?;
+        let target = match (current as i64).checked_add(delta) {
+            Some(target) if target >= 0 => target as u64,
+            _ => return Err(GenError::InvalidOffset),
+        };
+        out.seek(SeekFrom::Start(target))?;
+        let mut out = f(out)?;
+        out.seek(SeekFrom::Start(current))?;
+        Ok(out)
+    }
+}
+
+/// Applies the serializer, then shifts the reported position by `delta` without writing
+///
+/// The position saturates at `u64::MAX`, and `GenError::InvalidOffset` is returned if it would
+/// become negative. The underlying writer is not moved.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{map_position, string}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(map_position(12, string("abcd")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 16);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abcd"[..]);
+/// ```
+pub fn map_position<F, W: Write>(delta: i64, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| {
+        let mut out = f(out)?;
+        out.position = if delta >= 0 {
+            out.position.saturating_add(delta as u64)
+        } else {
+            out.position
+                .checked_sub(delta.unsigned_abs())
+                .ok_or(GenError::InvalidOffset)?
+        };
+        Ok(out)
+    }
+}
+
+/// Applies the serializer built by `f` from the number of bytes left in the output
+///
+/// `f` receives `None` if the output is unbounded.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{string, with_remaining}};
+///
+/// let mut buf = [0u8; 4];
+///
+/// {
+///   let verbose_or_compact = with_remaining(|remaining| match remaining {
+///     Some(n) if n < 7 => string("ok"),
+///     _ => string("success"),
+///   });
+///   let (_, pos) = gen(verbose_or_compact, &mut buf[..]).unwrap();
+///   assert_eq!(pos, 2);
+/// }
+///
+/// assert_eq!(&buf[..2], &b"ok"[..]);
+/// ```
+pub fn with_remaining<F, G, W: Write>(f: F) -> impl SerializeFn<W>
+where
+    F: Fn(Option<usize>) -> G,
+    G: SerializeFn<W>,
+    WriteContext<W>: RemainingCapacity,
+{
+    move |out: WriteContext<W>| {
+        let g = f(out.remaining());
+        g(out)
+    }
+}
+
+/// Reserves room for `bytes` more bytes in growable outputs, then applies the serializer
+///
+/// This avoids reallocations while serializing into a `Vec<u8>` when the output size is roughly
+/// known. It does nothing for fixed size outputs.
+///
+/// The writer must implement `Reserve`, whose default does nothing.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{reserve_hint, string}, multi::all};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let data = vec!["abcd", "efgh", "ijkl"];
+/// {
+///   let (buf, pos) = gen(reserve_hint(12, all(data.iter().map(string))), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 12);
+///   assert_eq!(buf.len(), 100 - 12);
+/// }
+///
+/// assert_eq!(&buf[..12], &b"abcdefghijkl"[..]);
+/// ```
+pub fn reserve_hint<F, W: Reserve>(bytes: usize, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        out.write.reserve(bytes);
+        f(out)
+    }
+}
+
+/// Applies the serializer only if at least `len` bytes are left in the output, otherwise writes
+/// nothing
+///
+/// Unbounded outputs always run the serializer.
+///
+/// ```rust
+/// use cookie_factory::{gen, sequence::tuple, combinator::{if_fits, string}};
+///
+/// let mut buf = [0u8; 6];
+///
+/// {
+///   let (_, pos) = gen(tuple((string("header"), if_fits(7, string("trailer")))), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 6);
+/// }
+///
+/// assert_eq!(&buf[..], &b"header"[..]);
+/// ```
+pub fn if_fits<F, W: Write>(len: usize, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+    WriteContext<W>: RemainingCapacity,
+{
+    move |out: WriteContext<W>| match out.remaining() {
+        Some(remaining) if remaining < len => Ok(out),
+        _ => f(out),
+    }
+}
+
+/// Applies the serializer built by `f` from a value
+///
+/// The value is cloned each time the serializer is run.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::map, bytes::be_u8};
+///
+/// #[derive(Clone, Copy)]
+/// enum Color { Red, Green }
+///
+/// impl Color {
+///   fn code(self) -> u8 {
+///     match self { Color::Red => 1, Color::Green => 2 }
+///   }
+/// }
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(map(Color::Green, |c| be_u8(c.code())), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 1);
+///   assert_eq!(buf.len(), 100 - 1);
+/// }
+///
+/// assert_eq!(&buf[..1], &[2u8][..]);
+/// ```
+pub fn map<T, F, G, W: Write>(value: T, f: F) -> impl SerializeFn<W>
+where
+    T: Clone,
+    F: Fn(T) -> G,
+    G: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| f(value.clone())(out)
+}
+
+/// Applies a serializer if the condition is true
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{cond, string}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(cond(true, string("abcd")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abcd"[..]);
+/// ```
+pub fn cond<F, W: Write>(condition: bool, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| {
+        if condition {
+            f(out)
+        } else {
+            Ok(out)
+        }
+    }
+}
+
+/// Parity expected by `with_parity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    /// the total number of set bits is even
+    Even,
+    /// the total number of set bits is odd
+    Odd,
+}
+
+/// `Write` wrapper counting the number of set bits in the data written through it
+///
+/// Used by `with_parity`.
+pub struct ParityWriter<W> {
+    inner: W,
+    ones: u64,
+}
+
+impl<W: Write> Write for ParityWriter<W> {
+    fn write(&mut self, data: &[u8]) -> crate::lib::std::io::Result<usize> {
+        let amt = self.inner.write(data)?;
+        self.ones += data[..amt]
+            .iter()
+            .map(|b| u64::from(b.count_ones()))
+            .sum::<u64>();
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> crate::lib::std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Reserve> Reserve for ParityWriter<W> {
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+}
+
+/// Applies a serializer, then writes a parity byte (`0` or `1`) so that the total number of
+/// set bits in the serializer's output and the parity byte matches `parity`
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{with_parity, slice, Parity}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(with_parity(slice(&[0x01u8, 0x03][..]), Parity::Even), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 3);
+///   assert_eq!(buf.len(), 100 - 3);
+/// }
+///
+/// assert_eq!(&buf[..3], &[0x01u8, 0x03, 0x01][..]);
+/// ```
+pub fn with_parity<F, W: Write>(f: F, parity: Parity) -> impl SerializeFn<W>
+where
+    F: SerializeFn<ParityWriter<W>>,
+{
+    move |out: WriteContext<W>| {
+        let WriteContext { write, position } = out;
+        let out = f(WriteContext {
+            write: ParityWriter {
+                inner: write,
+                ones: 0,
+            },
+            position,
+        })?;
+
+        let WriteContext {
+            write: ParityWriter { inner, ones },
+            position,
+        } = out;
+        let odd = ones % 2 == 1;
+        let parity_bit = match parity {
+            Parity::Even => odd,
+            Parity::Odd => !odd,
+        };
+
+        crate::bytes::be_u8(parity_bit as u8)(WriteContext {
+            write: inner,
+            position,
+        })
+    }
+}
+
+/// Applies a serializer if it is present
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{opt, string}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(opt(Some(string("abcd"))), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abcd"[..]);
+///
+/// {
+///   let name: Option<&str> = None;
+///   let (buf, pos) = gen(opt(name.map(string)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 0);
+///   assert_eq!(buf.len(), 100);
+/// }
+/// ```
+pub fn opt<F, W: Write>(value: Option<F>) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| match &value {
+        Some(f) => f(out),
+        None => Ok(out),
+    }
+}
+
+/// Applies the first serializer if the condition is true, the second one otherwise
+///
+/// This is the equivalent of the legacy `gen_if_else!` macro.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{cond_else, string}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(cond_else(true, string("abcd"), string("efg")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abcd"[..]);
+///
+/// {
+///   let (buf, pos) = gen(cond_else(false, string("abcd"), string("efg")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 3);
+///   assert_eq!(buf.len(), 100 - 3);
+/// }
+///
+/// assert_eq!(&buf[..3], &b"efg"[..]);
+/// ```
+pub fn cond_else<F, G, W: Write>(condition: bool, if_true: F, if_false: G) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+    G: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| {
+        if condition {
+            if_true(out)
+        } else {
+            if_false(out)
+        }
+    }
+}
+
+/// Applies a serializer, replacing any error it returns with `GenError::CustomError(code)`
+///
+/// ```rust
+/// use cookie_factory::{gen, GenError, combinator::{map_err, string}};
+///
+/// let mut buf = [0u8; 2];
+///
+/// match gen(map_err(string("abcd"), 42), &mut buf[..]) {
+///   Err(GenError::CustomError(code)) => assert_eq!(code, 42),
+///   _ => panic!("expected a custom error"),
+/// }
+/// ```
+pub fn map_err<F, W: Write>(f: F, code: u32) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| f(out).map_err(|_| GenError::CustomError(code))
+}
+
+/// Applies a serializer, then the serializer built by `after` from the number of bytes written
+/// by the first one
+///
+/// This is the equivalent of the `start: ... end:` checkpoints of the legacy `do_gen!` macro.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{checkpoint, string}, bytes::be_u8};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(checkpoint(string("abcd"), |len| be_u8(len as u8)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 5);
+///   assert_eq!(buf.len(), 100 - 5);
+/// }
+///
+/// assert_eq!(&buf[..5], &b"abcd\x04"[..]);
+/// ```
+pub fn checkpoint<F, A, G, W: Write>(f: F, after: A) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+    A: Fn(u64) -> G,
+    G: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| {
+        let start = out.position;
+        let out = f(out)?;
+        let written = out.position - start;
+        after(written)(out)
+    }
+}
+
+#[cfg(feature = "crc")]
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+#[cfg(feature = "crc")]
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// `Write` wrapper computing the CRC32 of the data written through it
+///
+/// Used by `crc32`.
+#[cfg(feature = "crc")]
+pub struct Crc32Writer<W> {
+    inner: W,
+    crc: u32,
+}
+
+#[cfg(feature = "crc")]
+impl<W: Write> Write for Crc32Writer<W> {
+    fn write(&mut self, data: &[u8]) -> crate::lib::std::io::Result<usize> {
+        let amt = self.inner.write(data)?;
+        for b in &data[..amt] {
+            self.crc = CRC32_TABLE[((self.crc ^ u32::from(*b)) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> crate::lib::std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "crc")]
+impl<W: Reserve> Reserve for Crc32Writer<W> {
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+}
+
+/// Applies a serializer, then writes the CRC32 (IEEE) of its output as a big endian `u32`
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{crc32, string}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(crc32(string("123456789")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 13);
+///   assert_eq!(buf.len(), 100 - 13);
+/// }
+///
+/// assert_eq!(&buf[..13], &b"123456789\xCB\xF4\x39\x26"[..]);
+/// ```
+#[cfg(feature = "crc")]
+pub fn crc32<F, W: Write>(f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<Crc32Writer<W>>,
+{
+    move |out: WriteContext<W>| {
+        let out = f(out.map_write(|inner| Crc32Writer {
+            inner,
+            crc: 0xFFFF_FFFF,
+        }))?;
+
+        let crc = !out.write.crc;
+        crate::bytes::be_u32(crc)(out.map_write(|w| w.inner))
+    }
+}
+
+/// Applies a serializer, then the serializer built by `trailer` from the bytes it wrote
+///
+/// This generalizes `crc32` to any checksum, MAC or hash. The body is first serialized into an
+/// internal `Vec<u8>`, which is then copied to the output, so it works with forward-only
+/// writers.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{string, with_trailer}, bytes::be_u8};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let xor = |data: &[u8]| be_u8(data.iter().fold(0, |acc, b| acc ^ b));
+///   let (buf, pos) = gen(with_trailer(string("abc"), xor), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abc\x60"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn with_trailer<F, T, G, W: Write>(body: F, trailer: T) -> impl SerializeFn<W>
+where
+    F: SerializeFn<Vec<u8>>,
+    T: Fn(&[u8]) -> G,
+    G: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        let buffered = body(WriteContext {
+            write: Vec::new(),
+            position: out.position,
+        })?;
+        let data = &buffered.write[..];
+        let len = data.len();
+        out = try_write!(out, len, data)?;
+        trailer(data)(out)
+    }
+}
+
+/// Reserves space for the `Before` combinator, applies the `Gen` combinator,
+/// then applies the `Before` combinator with the output from `Gen` onto the
+/// reserved space.
+///
+/// ```rust
+/// use cookie_factory::{gen, gen_simple, sequence::tuple, combinator::{back_to_the_buffer, string}, bytes::be_u8, bytes::be_u32};
+///
+/// let mut buf = [0; 9];
+/// gen_simple(tuple((
+///     back_to_the_buffer(
+///         4,
+///         move |buf| gen(string("test"), buf),
+///         move |buf, len| gen_simple(be_u32(len as u32), buf)
+///     ),
+///     be_u8(42)
+/// )), &mut buf[..]).unwrap();
+/// assert_eq!(&buf, &[0, 0, 0, 4, 't' as u8, 'e' as u8, 's' as u8, 't' as u8, 42]);
+/// ```
+pub fn back_to_the_buffer<W: BackToTheBuffer, Tmp, Gen, Before>(
+    reserved: usize,
+    gen: Gen,
+    before: Before,
+) -> impl SerializeFn<W>
+where
+    Gen: Fn(WriteContext<W>) -> Result<(WriteContext<W>, Tmp), GenError>,
+    Before: Fn(WriteContext<W>, Tmp) -> GenResult<W>,
+{
+    move |w: WriteContext<W>| W::reserve_write_use(w, reserved, &gen, &before)
+}
+
+/// Applies a serializer, prefixed by the number of bytes it wrote
+///
+/// `reserved` bytes are set aside for the prefix, then `body` is applied, and the serializer
+/// built by `write_len` from the length of the body output is written in the reserved space.
+/// It must write exactly `reserved` bytes, otherwise an error is returned.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{length_prefixed, string}, bytes::be_u16};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(
+///     length_prefixed(2, |len| be_u16(len as u16), string("abcd")),
+///     &mut buf[..],
+///   ).unwrap();
+///   assert_eq!(pos, 6);
+///   assert_eq!(buf.len(), 100 - 6);
+/// }
+///
+/// assert_eq!(&buf[..6], &b"\x00\x04abcd"[..]);
+/// ```
+pub fn length_prefixed<W: BackToTheBuffer, L, F, B>(
+    reserved: usize,
+    write_len: L,
+    body: B,
+) -> impl SerializeFn<W>
+where
+    L: Fn(u64) -> F,
+    F: SerializeFn<W>,
+    B: SerializeFn<W>,
+{
+    move |w: WriteContext<W>| {
+        W::reserve_write_use(
+            w,
+            reserved,
+            &|out: WriteContext<W>| {
+                let start = out.position;
+                let out = body(out)?;
+                let len = out.position - start;
+                Ok((out, len))
+            },
+            &|out, len| write_len(len)(out),
+        )
+    }
+}
+
+/// Applies a serializer, prefixed by the length of its output as a LEB128 varint
+///
+/// The width of the prefix depends on the length of the payload, so the space for it cannot be
+/// reserved ahead. The serializer is run once into a scratch `Vec<u8>` to measure it, then the
+/// prefix and the payload are written to the output. Positions inside the payload start at 0,
+/// as if it was serialized on its own.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{string, varint_prefixed}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(varint_prefixed(string("abcd")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 5);
+///   assert_eq!(buf.len(), 100 - 5);
+/// }
+///
+/// assert_eq!(&buf[..5], &b"\x04abcd"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn varint_prefixed<F, W: Write>(f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<Vec<u8>>,
+{
+    move |out: WriteContext<W>| {
+        let payload = gen_to_vec(&f)?;
+        let out = crate::bytes::leb128_u64(payload.len() as u64)(out)?;
+        slice(payload)(out)
+    }
+}
+
+/// Writes a protobuf field tag, `(field_number << 3) | wire_type`, as a LEB128 varint
+///
+/// Returns `GenError::InvalidValue` if `field_number` is 0 or does not fit in 29 bits, or if
+/// `wire_type` does not fit in 3 bits.
+///
+/// ```rust
+/// use cookie_factory::{gen, sequence::pair, combinator::pb_tag, bytes::leb128_u64};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   // field 1, varint 150
+///   let (buf, pos) = gen(pair(pb_tag(1, 0), leb128_u64(150)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 3);
+///   assert_eq!(buf.len(), 100 - 3);
+/// }
+///
+/// assert_eq!(&buf[..3], &[0x08u8, 0x96, 0x01][..]);
+/// ```
+pub fn pb_tag<W: Write>(field_number: u32, wire_type: u8) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| {
+        if field_number == 0 || field_number >= 1 << 29 || wire_type > 7 {
+            return Err(GenError::InvalidValue);
+        }
+        crate::bytes::leb128_u64(u64::from(field_number) << 3 | u64::from(wire_type))(out)
+    }
+}
+
+/// Writes a length delimited protobuf field: the tag with wire type 2, the length of the
+/// serializer output as a LEB128 varint, then the serializer output
+///
+/// As with `varint_prefixed`, the serializer is run once into a scratch `Vec<u8>` to measure
+/// its output first, so nested messages are only serialized once.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{pb_length_delimited, string}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(pb_length_delimited(2, string("testing")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 9);
+///   assert_eq!(buf.len(), 100 - 9);
+/// }
+///
+/// assert_eq!(&buf[..9], &b"\x12\x07testing"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn pb_length_delimited<F, W: Write>(field_number: u32, body: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<Vec<u8>>,
+{
+    crate::sequence::pair(pb_tag(field_number, 2), varint_prefixed(body))
+}
+
+/// Applies a serializer and prints to stderr how many bytes it wrote and where
+///
+/// The output of the serializer is left unchanged, which makes this convenient to debug a
+/// sub-serializer in place.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{inspect, string}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   // prints "greeting: wrote 5 bytes at offset 0"
+///   let (buf, pos) = gen(inspect("greeting", string("hello")), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 5);
+///   assert_eq!(buf.len(), 100 - 5);
+/// }
+///
+/// assert_eq!(&buf[..5], &b"hello"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn inspect<F, W: Write>(label: &'static str, f: F) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+{
+    move |out: WriteContext<W>| {
+        let start = out.position;
+        let out = f(out)?;
+        eprintln!(
+            "{}: wrote {} bytes at offset {}",
+            label,
+            out.position - start,
+            start
+        );
+        Ok(out)
+    }
+}
+
+/// Records the current position under `name` in `checkpoints`, without writing anything
+///
+/// ```rust
+/// use cookie_factory::{gen, Checkpoints, sequence::tuple, combinator::{record_offset, string}};
+///
+/// let checkpoints = Checkpoints::new();
+/// let mut buf = [0u8; 100];
+///
+/// gen(tuple((string("abcd"), record_offset(&checkpoints, "body"))), &mut buf[..]).unwrap();
+///
+/// assert_eq!(checkpoints.get("body"), Some(4));
+/// ```
+#[cfg(feature = "std")]
+pub fn record_offset<W: Write>(
+    checkpoints: &Checkpoints,
+    name: &'static str,
+) -> impl SerializeFn<W> {
+    let checkpoints = checkpoints.clone();
+
+    move |out: WriteContext<W>| {
+        checkpoints.record(name, out.position);
+        Ok(out)
+    }
+}
+
+/// Writes the 4 octets of an IPv4 address to the output
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::ipv4};
+/// use std::net::Ipv4Addr;
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(ipv4(Ipv4Addr::new(127, 0, 0, 1)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &[127u8, 0, 0, 1][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn ipv4<W: Write>(addr: std::net::Ipv4Addr) -> impl SerializeFn<W> {
+    slice(addr.octets())
+}
+
+/// Writes the 16 octets of an IPv6 address to the output, each segment in big endian order
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::ipv6};
+/// use std::net::Ipv6Addr;
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+///   let (buf, pos) = gen(ipv6(addr), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 16);
+///   assert_eq!(buf.len(), 100 - 16);
+/// }
+///
+/// assert_eq!(&buf[..16], &[0x20u8, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn ipv6<W: Write>(addr: std::net::Ipv6Addr) -> impl SerializeFn<W> {
+    slice(addr.octets())
+}
+
+/// Writes the octets of an IP address to the output: 4 for IPv4, 16 for IPv6
+///
+/// No tag is written to distinguish the two versions.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::ip};
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(ip(IpAddr::V4(Ipv4Addr::LOCALHOST)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &[127u8, 0, 0, 1][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn ip<W: Write>(addr: std::net::IpAddr) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| match addr {
+        std::net::IpAddr::V4(addr) => ipv4(addr)(out),
+        std::net::IpAddr::V6(addr) => ipv6(addr)(out),
+    }
+}
+
+/// Writes the 4 octets of an IPv4 socket address, followed by its port in big endian order
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::socket_addr_v4};
+/// use std::net::{Ipv4Addr, SocketAddrV4};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let addr = SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 8080);
+///   let (buf, pos) = gen(socket_addr_v4(addr), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 6);
+///   assert_eq!(buf.len(), 100 - 6);
+/// }
+///
+/// assert_eq!(&buf[..6], &[1u8, 2, 3, 4, 0x1f, 0x90][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn socket_addr_v4<W: Write>(addr: std::net::SocketAddrV4) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| {
+        let out = ipv4(*addr.ip())(out)?;
+        crate::bytes::be_u16(addr.port())(out)
+    }
+}
+
+/// Writes the 16 octets of an IPv6 socket address, followed by its port in big endian order
+///
+/// The flow info and scope id are not written.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::socket_addr_v6};
+/// use std::net::{Ipv6Addr, SocketAddrV6};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let addr = SocketAddrV6::new(Ipv6Addr::LOCALHOST, 8080, 0, 0);
+///   let (buf, pos) = gen(socket_addr_v6(addr), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 18);
+///   assert_eq!(buf.len(), 100 - 18);
+/// }
+///
+/// assert_eq!(&buf[..18], &[0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x1f, 0x90][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn socket_addr_v6<W: Write>(addr: std::net::SocketAddrV6) -> impl SerializeFn<W> {
+    move |out: WriteContext<W>| {
+        let out = ipv6(*addr.ip())(out)?;
+        crate::bytes::be_u16(addr.port())(out)
+    }
+}
+
+/// Writes the number of whole seconds in a `Duration` as a big endian `u64`
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::duration_secs};
+/// use std::time::Duration;
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(duration_secs(Duration::from_secs(5)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &[0u8, 0, 0, 0, 0, 0, 0, 5][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn duration_secs<W: Write>(d: std::time::Duration) -> impl SerializeFn<W> {
+    crate::bytes::be_u64(d.as_secs())
+}
+
+/// Writes the number of whole milliseconds in a `Duration` as a big endian `u64`
+///
+/// Returns `GenError::InvalidValue` if the value does not fit in a `u64`.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::duration_millis};
+/// use std::time::Duration;
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(duration_millis(Duration::from_secs(5)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &[0u8, 0, 0, 0, 0, 0, 19, 136][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn duration_millis<W: Write>(d: std::time::Duration) -> impl SerializeFn<W> {
+    use std::convert::TryFrom;
+
+    move |out: WriteContext<W>| match u64::try_from(d.as_millis()) {
+        Ok(v) => crate::bytes::be_u64(v)(out),
+        Err(_) => Err(GenError::InvalidValue),
+    }
+}
+
+/// Writes the number of whole microseconds in a `Duration` as a big endian `u64`
+///
+/// Returns `GenError::InvalidValue` if the value does not fit in a `u64`.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::duration_micros};
+/// use std::time::Duration;
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(duration_micros(Duration::from_secs(5)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &[0u8, 0, 0, 0, 0, 76, 75, 64][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn duration_micros<W: Write>(d: std::time::Duration) -> impl SerializeFn<W> {
+    use std::convert::TryFrom;
+
+    move |out: WriteContext<W>| match u64::try_from(d.as_micros()) {
+        Ok(v) => crate::bytes::be_u64(v)(out),
+        Err(_) => Err(GenError::InvalidValue),
+    }
+}
+
+/// Writes the number of seconds between the Unix epoch and a `SystemTime` as a big endian `i64`
+///
+/// Times before the epoch are written as negative values, rounded down to the previous second.
+/// Returns `GenError::InvalidValue` if the value does not fit in an `i64`.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::system_time_unix};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let t = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+///   let (buf, pos) = gen(system_time_unix(t), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &[0u8, 0, 0, 0, 0x3b, 0x9a, 0xca, 0x00][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn system_time_unix<W: Write>(t: std::time::SystemTime) -> impl SerializeFn<W> {
+    use std::convert::TryFrom;
+
+    move |out: WriteContext<W>| {
+        let secs = match t.duration_since(std::time::UNIX_EPOCH) {
+            Ok(d) => i64::try_from(d.as_secs()).ok(),
+            Err(e) => {
+                let d = e.duration();
+                let rounding = if d.subsec_nanos() > 0 { 1 } else { 0 };
+                i64::try_from(d.as_secs())
+                    .ok()
+                    .and_then(|s| s.checked_neg())
+                    .and_then(|s| s.checked_sub(rounding))
+            }
+        };
+
+        match secs {
+            Some(secs) => crate::bytes::be_i64(secs)(out),
+            None => Err(GenError::InvalidValue),
+        }
+    }
+}
+
+/// Writes a `SystemTime` as a 64 bit NTP timestamp: 32 bit seconds since 1900-01-01, then a
+/// 32 bit binary fraction of a second, in big endian order
+///
+/// As specified by NTP, the seconds wrap around every 2^32 seconds (in 2036, 2172, etc). Returns
+/// `GenError::InvalidValue` for times before 1900.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::ntp_timestamp};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let t = UNIX_EPOCH + Duration::from_millis(500);
+///   let (buf, pos) = gen(ntp_timestamp(t), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// // 2208988800 seconds between 1900 and 1970, and half a second
+/// assert_eq!(&buf[..8], &[0x83u8, 0xaa, 0x7e, 0x80, 0x80, 0, 0, 0][..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn ntp_timestamp<W: Write>(t: std::time::SystemTime) -> impl SerializeFn<W> {
+    const NTP_TO_UNIX_SECS: u64 = 2_208_988_800;
+
+    move |out: WriteContext<W>| {
+        let ntp_epoch = std::time::UNIX_EPOCH - std::time::Duration::from_secs(NTP_TO_UNIX_SECS);
+        let since = match t.duration_since(ntp_epoch) {
+            Ok(d) => d,
+            Err(_) => return Err(GenError::InvalidValue),
+        };
+
+        let secs = since.as_secs() as u32;
+        let fraction = ((u64::from(since.subsec_nanos()) << 32) / 1_000_000_000) as u32;
+        let out = crate::bytes::be_u32(secs)(out)?;
+        crate::bytes::be_u32(fraction)(out)
+    }
+}
+
+/// Accumulator packing bit fields into bytes, most significant bit first
+///
+/// Complete bytes are written to the output as soon as they are available. Used by `bits`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BitWriter {
+    acc: u8,
+    used: u8,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the `width` lower bits of `value`, writing the bytes completed to the output
+    ///
+    /// Returns `GenError::InvalidValue` if `value` does not fit in `width` bits, or if `width` is
+    /// more than 32.
+    pub fn write_bits<W: Write>(
+        &mut self,
+        mut out: WriteContext<W>,
+        value: u32,
+        width: u8,
+    ) -> GenResult<W> {
+        if width > 32 || (width < 32 && value >> width != 0) {
+            return Err(GenError::InvalidValue);
+        }
+
+        for i in (0..width).rev() {
+            self.acc = (self.acc << 1) | ((value >> i) & 1) as u8;
+            self.used += 1;
+            if self.used == 8 {
+                let len = 1;
+                out = try_write!(out, len, &[self.acc][..])?;
+                self.acc = 0;
+                self.used = 0;
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Writes the pending partial byte, if any, padded with zeros in the low bits
+    pub fn finish<W: Write>(self, mut out: WriteContext<W>) -> GenResult<W> {
+        if self.used == 0 {
+            return Ok(out);
+        }
+
+        let len = 1;
+        try_write!(out, len, &[self.acc << (8 - self.used)][..])
+    }
+}
+
+/// Packs a list of `(value, width in bits)` fields, most significant bit first
+///
+/// The last byte is padded with zeros if the total width is not a multiple of 8. Returns
+/// `GenError::InvalidValue`, without writing anything, if a value does not fit in its width.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::bits};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(bits(&[(0b1, 1), (0b010, 3), (0b1111, 4)]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 1);
+///   assert_eq!(buf.len(), 100 - 1);
+/// }
+///
+/// assert_eq!(&buf[..1], &[0b1_010_1111u8][..]);
+/// ```
+pub fn bits<'a, W: Write + 'a>(fields: &'a [(u32, u8)]) -> impl SerializeFn<W> + 'a {
+    move |mut out: WriteContext<W>| {
+        if fields
+            .iter()
+            .any(|&(value, width)| width > 32 || (width < 32 && value >> width != 0))
+        {
+            return Err(GenError::InvalidValue);
+        }
+
+        let mut writer = BitWriter::new();
+        for &(value, width) in fields {
+            out = writer.write_bits(out, value, width)?;
+        }
+        writer.finish(out)
+    }
+}
+
+/// Queue of serializers written incrementally by the `stream` combinator
+///
+/// Serializers can be pushed at any time, and are written in order, then discarded, the next
+/// time the `stream` serializer is applied.
+#[cfg(feature = "std")]
+pub struct Stream<G> {
+    pending: crate::lib::std::cell::RefCell<std::collections::VecDeque<G>>,
+}
+
+#[cfg(feature = "std")]
+impl<G> Default for Stream<G> {
+    fn default() -> Self {
+        Stream {
+            pending: Default::default(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G> Stream<G> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a serializer at the end of the queue
+    pub fn push(&self, g: G) {
+        self.pending.borrow_mut().push_back(g);
+    }
+
+    /// Returns the number of serializers waiting to be written
+    pub fn len(&self) -> usize {
+        self.pending.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.borrow().is_empty()
+    }
+}
+
+/// Writes the serializers currently queued in a `Stream`, removing them from the queue
+///
+/// If one of the serializers fails, it stays at the front of the queue along with the following
+/// ones.
+///
+/// ```rust
+/// use cookie_factory::{gen, combinator::{stream, string, Stream}};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let s = Stream::new();
+///   s.push(string("ab"));
+///   s.push(string("cd"));
+///
+///   let (buf, pos) = gen(stream(&s), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert!(s.is_empty());
+///
+///   s.push(string("ef"));
+///   let (_, pos) = gen(stream(&s), buf).unwrap();
+///   assert_eq!(pos, 2);
+/// }
+///
+/// assert_eq!(&buf[..6], &b"abcdef"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn stream<G: SerializeFn<W>, W: Write>(s: &Stream<G>) -> impl SerializeFn<W> + '_ {
+    move |mut out: WriteContext<W>| loop {
+        let next = s.pending.borrow_mut().pop_front();
+        match next {
+            None => return Ok(out),
+            Some(g) => match g(out) {
+                Ok(o) => out = o,
+                Err(e) => {
+                    s.pending.borrow_mut().push_front(g);
+                    return Err(e);
+                }
+            },
+        }
+    }
+}
+
+//missing combinators:
+//or
+//empty
+//length_value
+//text print
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bytes::{be_u16, be_u32, be_u8};
+    use crate::sequence::{pair, tuple};
+
+    #[test]
+    fn test_gen_with_length() {
+        let mut buf = [0; 8];
+        {
+            let (len_buf, buf) = buf.split_at_mut(4);
+            let (_, pos) = gen(string("test"), buf).unwrap();
+            gen(be_u32(pos as u32), len_buf).unwrap();
+        }
+        assert_eq!(&buf, &[0, 0, 0, 4, b't', b'e', b's', b't']);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_skip_vec() {
+        let (buf, pos) = gen(pair(skip(4), be_u8(42)), Vec::new()).unwrap();
+
+        assert_eq!(pos, 5);
+        assert_eq!(&buf[..], &[0, 0, 0, 0, 42]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_gen_with_offsets() {
+        let (buf, pos, checkpoints) = gen_with_offsets(
+            |c| {
+                tuple((
+                    be_u8(1),
+                    record_offset(c, "header_end"),
+                    string("test"),
+                    record_offset(c, "body_end"),
+                    be_u8(2),
+                ))
+            },
+            Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(pos, 6);
+        assert_eq!(&buf[..], &[1, b't', b'e', b's', b't', 2]);
+        assert_eq!(checkpoints.get("header_end"), Some(1));
+        assert_eq!(checkpoints.get("body_end"), Some(5));
+        assert_eq!(checkpoints.to_map().len(), 2);
+    }
+
+    struct ThreeBytesWriter<W>(W);
+
+    impl<W: Write> Write for ThreeBytesWriter<W> {
+        fn write(&mut self, data: &[u8]) -> crate::lib::std::io::Result<usize> {
+            let len = crate::lib::std::cmp::min(data.len(), 3);
+            self.0.write(&data[..len])
+        }
+
+        fn flush(&mut self) -> crate::lib::std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_slice_all() {
+        let mut buf = [0u8; 8];
+
+        {
+            let out = ThreeBytesWriter(&mut buf[..]);
+            match gen(slice(&b"abcdefgh"[..]), out) {
+                Err(GenError::BufferTooSmall(5)) => {}
+                Err(e) => panic!("unexpected error: {:?}", e),
+                Ok(_) => panic!("should have failed"),
+            }
+
+            let out = ThreeBytesWriter(&mut buf[..]);
+            let (_, pos) = gen(slice_all(&b"abcdefgh"[..]), out).unwrap();
+            assert_eq!(pos, 8);
+        }
+
+        assert_eq!(&buf, b"abcdefgh");
+
+        let mut buf = [0u8; 5];
+        let out = ThreeBytesWriter(&mut buf[..]);
+        match gen(slice_all(&b"abcdefgh"[..]), out) {
+            Err(GenError::BufferTooSmall(3)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_raw_fragment() {
+        let cached_header = [0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 1];
+        let mut buf = [0u8; 10];
+
+        let (_, pos) = gen(
+            tuple((raw_fragment(&cached_header[..]), be_u16(0x1234))),
+            &mut buf[..],
+        )
+        .unwrap();
+
+        assert_eq!(pos, 10);
+        assert_eq!(&buf, &[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 1, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn test_map_err() {
+        let mut buf = [0u8; 2];
+
+        match gen(be_u32(1), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(2)) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+
+        match gen(map_err(be_u32(1), 7), &mut buf[..]) {
+            Err(GenError::CustomError(7)) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+
+        let (_, pos) = gen(map_err(be_u16(1), 7), &mut buf[..]).unwrap();
+        assert_eq!(pos, 2);
+    }
+
+    #[test]
+    fn test_base64() {
+        let mut buf = [0u8; 12];
+
+        let (_, pos) = gen(base64(&b"hello!!"[..]), &mut buf[..]).unwrap();
+        assert_eq!(pos, 12);
+        assert_eq!(&buf, b"aGVsbG8hIQ==");
+
+        let (_, pos) = gen(base64(&[0xFB, 0xFF, 0xBF][..]), &mut buf[..]).unwrap();
+        assert_eq!(pos, 4);
+        assert_eq!(&buf[..4], b"+/+/");
+
+        let (_, pos) = gen(base64(&b""[..]), &mut buf[..]).unwrap();
+        assert_eq!(pos, 0);
+    }
+
+    #[test]
+    fn test_string_fixed() {
+        let mut buf = [0xFFu8; 40];
+
+        let (_, pos) = gen(string_fixed("ab", 40, 0), &mut buf[..]).unwrap();
+        assert_eq!(pos, 40);
+        assert_eq!(&buf[..2], b"ab");
+        assert!(buf[2..].iter().all(|b| *b == 0));
+
+        let (_, pos) = gen(string_fixed("abcd", 4, 0), &mut buf[..]).unwrap();
+        assert_eq!(pos, 4);
+        assert_eq!(&buf[..4], b"abcd");
+
+        let (_, pos) = gen(string_fixed("abcdef", 4, 0), &mut buf[..]).unwrap();
+        assert_eq!(pos, 4);
+        assert_eq!(&buf[..4], b"abcd");
+
+        // 'é' is 2 bytes long and must not be split
+        let (_, pos) = gen(string_fixed("abcé", 4, b' '), &mut buf[..]).unwrap();
+        assert_eq!(pos, 4);
+        assert_eq!(&buf[..4], b"abc ");
+
+        match gen(string_fixed("é", 1, b' '), &mut buf[..]) {
+            Err(GenError::InvalidValue) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+    }
+
+    #[test]
+    fn test_cstr() {
+        let mut buf = [0u8; 6];
+
+        let (_, pos) = gen(cstr("hello"), &mut buf[..]).unwrap();
+        assert_eq!(pos, 6);
+        assert_eq!(&buf, b"hello\0");
+
+        match gen(cstr("he\0lo"), &mut buf[..]) {
+            Err(GenError::InvalidValue) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+
+        match gen(cstr("hello!"), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(1)) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+    }
+
+    #[test]
+    fn test_reserve_patch_at() {
+        let mut buf = [0xFF; 10];
+        {
+            let cursor = crate::lib::std::io::Cursor::new(&mut buf[..]);
+            let (cursor, pos) = gen(
+                tuple((
+                    be_u8(1),
+                    reserve(4),
+                    be_u8(2),
+                    string("test"),
+                    patch_at(1, be_u32(5)),
+                )),
+                cursor,
+            )
+            .unwrap();
+            assert_eq!(pos, 10);
+            assert_eq!(cursor.position(), 10);
+        }
+        assert_eq!(&buf, &[1, 0, 0, 0, 5, 2, b't', b'e', b's', b't']);
+    }
+
+    #[test]
+    fn test_with_parity() {
+        // 0xDEADBEEF has 24 set bits
+        let mut buf = [0u8; 5];
+        let (_, pos) = gen(with_parity(be_u32(0xDEADBEEF), Parity::Even), &mut buf[..]).unwrap();
+        assert_eq!(pos, 5);
+        assert_eq!(&buf, &[0xDE, 0xAD, 0xBE, 0xEF, 0]);
+
+        // 0xDF has 7 set bits
+        let mut buf = [0u8; 2];
+        let (_, pos) = gen(with_parity(be_u8(0xDF), Parity::Even), &mut buf[..]).unwrap();
+        assert_eq!(pos, 2);
+        assert_eq!(&buf, &[0xDF, 1]);
+
+        let (_, pos) = gen(with_parity(be_u8(0xDF), Parity::Odd), &mut buf[..]).unwrap();
+        assert_eq!(pos, 2);
+        assert_eq!(&buf, &[0xDF, 0]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_skip_cursor_vec() {
+        let mut cursor = crate::lib::std::io::Cursor::new(vec![1, 2]);
+        cursor.set_position(1);
+
+        let (cursor, pos) = gen(tuple((skip(3), be_u8(42), skip(2))), cursor).unwrap();
+
+        assert_eq!(pos, 6);
+        assert_eq!(cursor.position(), 7);
+        assert_eq!(&cursor.get_ref()[..], &[1, 2, 0, 0, 42, 0, 0]);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut buf = [0u8; 10];
+
+        let (_, pos) = gen(
+            tuple((
+                be_u8(1),
+                checkpoint(tuple((string("test"), be_u8(0))), |len| be_u32(len as u32)),
+            )),
+            &mut buf[..],
+        )
+        .unwrap();
+
+        assert_eq!(pos, 10);
+        assert_eq!(&buf, &[1, b't', b'e', b's', b't', 0, 0, 0, 0, 5]);
+    }
+
+    #[cfg(feature = "crc")]
+    #[test]
+    fn test_crc32() {
+        let mut buf = [0u8; 48];
+
+        let (_, pos) = gen(
+            crc32(string("The quick brown fox jumps over the lazy dog")),
+            &mut buf[..],
+        )
+        .unwrap();
+
+        assert_eq!(pos, 47);
+        assert_eq!(&buf[43..47], &0x414F_A339u32.to_be_bytes()[..]);
+
+        let (_, pos) = gen(crc32(string("")), &mut buf[..]).unwrap();
+        assert_eq!(pos, 4);
+        assert_eq!(&buf[..4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_back_to_the_buffer() {
+        let mut buf = [0; 9];
+
+        let new_buf = gen_simple(
             tuple((
                 back_to_the_buffer(
                     4,
@@ -214,7 +2342,90 @@ mod test {
         )
         .unwrap();
 
-        assert_eq!(&buf[..], &[0, 0, 0, 4, b't', b'e', b's', b't', 42]);
+        assert_eq!(&buf[..], &[0, 0, 0, 4, b't', b'e', b's', b't', 42]);
+    }
+
+    #[test]
+    fn test_length_prefixed_slice() {
+        let mut buf = [0u8; 10];
+        {
+            let (_, pos) = gen(
+                tuple((
+                    length_prefixed(4, |len| be_u32(len as u32), string("test")),
+                    be_u8(42),
+                )),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 9);
+        }
+        assert_eq!(&buf[..9], &[0, 0, 0, 4, b't', b'e', b's', b't', 42]);
+
+        let mut buf = [0u8; 10];
+        let res = gen(
+            length_prefixed(4, |len| be_u16(len as u16), string("test")),
+            &mut buf[..],
+        );
+        assert!(matches!(res, Err(GenError::BufferTooBig(2))));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_length_prefixed_vec() {
+        let (buf, pos) = gen(
+            tuple((
+                be_u8(1),
+                length_prefixed(
+                    2,
+                    |len| be_u16(len as u16),
+                    tuple((
+                        string("ab"),
+                        length_prefixed(1, |len| be_u8(len as u8), string("cde")),
+                    )),
+                ),
+            )),
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(pos, 9);
+        assert_eq!(&buf[..], &[1, 0, 6, b'a', b'b', 3, b'c', b'd', b'e']);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_back_to_the_buffer_vec_size_mismatch() {
+        let res = gen_simple(
+            back_to_the_buffer(
+                4,
+                move |buf| gen(string("test"), buf),
+                move |buf, len| gen_simple(be_u16(len as u16), buf),
+            ),
+            Vec::new(),
+        );
+        assert!(matches!(res, Err(GenError::BufferTooBig(2))));
+
+        let res = gen_simple(
+            back_to_the_buffer(
+                2,
+                move |buf| gen(string("test"), buf),
+                move |buf, len| gen_simple(be_u32(len as u32), buf),
+            ),
+            Vec::new(),
+        );
+        assert!(matches!(res, Err(GenError::BufferTooSmall(2))));
+
+        let res = gen_simple(
+            back_to_the_buffer(
+                4,
+                move |mut buf: WriteContext<Vec<u8>>| {
+                    buf.write.clear();
+                    Ok((buf, ()))
+                },
+                move |buf, ()| gen_simple(be_u32(0), buf),
+            ),
+            Vec::new(),
+        );
+        assert!(matches!(res, Err(GenError::InvalidOffset)));
     }
 
     #[test]
@@ -262,4 +2473,637 @@ mod test {
         }
         assert_eq!(&buf, &[64, 0, 0, 0, 4, b't', b'e', b's', b't', 42]);
     }
+
+    #[test]
+    fn test_seek_to() {
+        let mut buf = [0u8; 8];
+
+        {
+            let cursor = crate::lib::std::io::Cursor::new(&mut buf[..]);
+            let (cursor, pos) = gen(
+                tuple((reserve(4), string("body"), seek_to(0, be_u32(0xcafe_babe)))),
+                cursor,
+            )
+            .unwrap();
+            assert_eq!(pos, 8);
+            assert_eq!(cursor.position(), 8);
+        }
+
+        assert_eq!(&buf[..], &b"\xca\xfe\xba\xbebody"[..]);
+    }
+
+    #[test]
+    fn test_seek_to_past_end() {
+        let mut buf = [0u8; 4];
+        let cursor = crate::lib::std::io::Cursor::new(&mut buf[..]);
+        match gen(tuple((string("ab"), seek_to(3, be_u16(1)))), cursor) {
+            Err(GenError::BufferTooSmall(1)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_seek_rel() {
+        let mut buf = [0u8; 6];
+
+        {
+            let cursor = crate::lib::std::io::Cursor::new(&mut buf[..]);
+            let (cursor, pos) = gen(
+                tuple((
+                    string("ab"),
+                    seek_rel(2, string("ef")),
+                    string("cd"),
+                    seek_rel(-4, be_u8(b'X')),
+                )),
+                cursor,
+            )
+            .unwrap();
+            assert_eq!(pos, 4);
+            assert_eq!(cursor.position(), 4);
+        }
+
+        assert_eq!(&buf[..], &b"Xbcdef"[..]);
+    }
+
+    #[test]
+    fn test_seek_rel_negative() {
+        let mut buf = [0u8; 4];
+        let cursor = crate::lib::std::io::Cursor::new(&mut buf[..]);
+        match gen(tuple((string("ab"), seek_rel(-3, be_u8(1)))), cursor) {
+            Err(GenError::InvalidOffset) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_pad_with() {
+        let mut buf = [0u8; 16];
+        {
+            let (_, pos) = gen(
+                tuple((
+                    be_u8(1),
+                    pad_with(4, be_u8(0xff)),
+                    pad_with(4, be_u8(0xee)),
+                    be_u16(0x0203),
+                    pad_with(8, be_u16(0xabcd)),
+                )),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 8);
+        }
+        assert_eq!(&buf[..8], &[1, 0xff, 0xff, 0xff, 2, 3, 0xab, 0xcd]);
+
+        for multiple in [4, 0] {
+            let mut buf = [0u8; 16];
+            match gen(
+                pair(string("abc"), pad_with(multiple, be_u16(0))),
+                &mut buf[..],
+            ) {
+                Err(GenError::InvalidValue) => {}
+                Err(e) => panic!("unexpected error: {:?}", e),
+                Ok(_) => panic!("should have failed"),
+            }
+        }
+
+        let mut buf = [0u8; 16];
+        match gen(pair(string("abc"), pad_with(4, string(""))), &mut buf[..]) {
+            Err(GenError::InvalidValue) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_map_position() {
+        let mut buf = [0u8; 4];
+
+        {
+            let (_, pos) = gen(
+                tuple((string("ab"), map_position(-2, string("c")), string("d"))),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 2);
+        }
+        assert_eq!(&buf, b"abcd");
+
+        let mut buf = [0u8; 4];
+        let (_, pos) = gen(
+            map_position(i64::MAX, map_position(i64::MAX, string("ab"))),
+            &mut buf[..],
+        )
+        .unwrap();
+        assert_eq!(pos, u64::MAX);
+
+        let mut buf = [0u8; 4];
+        match gen(map_position(-3, string("ab")), &mut buf[..]) {
+            Err(GenError::InvalidOffset) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_with_remaining() {
+        let mut buf = [0u8; 5];
+
+        {
+            let (rest, pos) = gen(
+                tuple((
+                    string("ab"),
+                    with_remaining(|remaining| {
+                        assert_eq!(remaining, Some(3));
+                        be_u8(remaining.unwrap() as u8)
+                    }),
+                )),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 3);
+            assert_eq!(rest.len(), 2);
+        }
+
+        assert_eq!(&buf[..3], &b"ab\x03"[..]);
+
+        let cursor = crate::lib::std::io::Cursor::new(&mut buf[..]);
+        let (_, pos) = gen(
+            with_remaining(|remaining| {
+                assert_eq!(remaining, Some(5));
+                string("x")
+            }),
+            cursor,
+        )
+        .unwrap();
+        assert_eq!(pos, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_with_remaining_unbounded() {
+        let (out, _) = gen(
+            with_remaining(|remaining| {
+                assert_eq!(remaining, None);
+                string("x")
+            }),
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(&out[..], &b"x"[..]);
+    }
+
+    #[test]
+    fn test_short_write_reports_shortfall() {
+        let data = [1u8; 6];
+
+        let mut buf = [0u8; 4];
+        match gen(slice(&data[..]), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(2)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+
+        let mut buf = [0u8; 4];
+        match gen(tuple((slice(&data[..3]), slice(&data[..3]))), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(2)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+
+        let mut buf = [0u8; 36];
+        match gen(reserve(40), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(4)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+
+        let mut buf = [0u8; 4];
+        match gen(string_fixed("ab", 6, b' '), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(2)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_hex() {
+        let mut buf = [0u8; 41];
+
+        {
+            let (_, pos) = gen(
+                tuple((
+                    hex(0u8),
+                    string(","),
+                    hex(-1i8),
+                    string(","),
+                    hex(u128::MAX),
+                    string(","),
+                    hex_lower(0xABCu32),
+                )),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 41);
+        }
+
+        assert_eq!(&buf[..], &b"0,FF,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF,abc"[..]);
+
+        struct Id(u16);
+
+        impl crate::lib::std::fmt::UpperHex for Id {
+            fn fmt(
+                &self,
+                f: &mut crate::lib::std::fmt::Formatter<'_>,
+            ) -> crate::lib::std::fmt::Result {
+                write!(f, "ID{:04X}", self.0)
+            }
+        }
+
+        let mut buf = [0u8; 8];
+        {
+            let n = 0x2Au8;
+            let (_, pos) = gen(tuple((hex(&n), hex(Id(0xBE)))), &mut buf[..]).unwrap();
+            assert_eq!(pos, 8);
+        }
+        assert_eq!(&buf[..], &b"2AID00BE"[..]);
+
+        let mut buf = [0u8; 1];
+        match gen(hex(0x2A), &mut buf[..]) {
+            Err(GenError::IoError(_)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_varint_prefixed() {
+        use crate::multi::{all, all_once};
+
+        for &len in [0usize, 1, 127, 128, 300, 16_384].iter() {
+            let mut buf = [0u8; 16_400];
+            let data = [0x55u8; 16_384];
+
+            let pos = {
+                let (_, pos) = gen(varint_prefixed(slice(&data[..len])), &mut buf[..]).unwrap();
+                pos as usize
+            };
+
+            let prefix_len = match len {
+                0..=127 => 1,
+                128..=16_383 => 2,
+                _ => 3,
+            };
+            assert_eq!(pos, prefix_len + len);
+
+            let mut prefix = [0u8; 3];
+            gen(crate::bytes::leb128_u64(len as u64), &mut prefix[..]).unwrap();
+            assert_eq!(&buf[..prefix_len], &prefix[..prefix_len]);
+            assert!(buf[prefix_len..pos].iter().all(|b| *b == 0x55));
+        }
+
+        let mut buf = [0u8; 8];
+        let words = ["ab", "cd", "e"];
+        {
+            let (_, pos) = gen(
+                tuple((varint_prefixed(all(words.iter().map(string))), be_u8(0xff))),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 7);
+        }
+        assert_eq!(&buf[..7], &b"\x05abcde\xff"[..]);
+
+        // the payload is only run once
+        let mut buf = [0u8; 8];
+        {
+            let s = Stream::new();
+            s.push(string("ab"));
+            s.push(string("cde"));
+            let (_, pos) = gen(varint_prefixed(stream(&s)), &mut buf[..]).unwrap();
+            assert_eq!(pos, 6);
+            assert!(s.is_empty());
+        }
+        assert_eq!(&buf[..6], &b"\x05abcde"[..]);
+
+        let mut buf = [0u8; 8];
+        {
+            let (_, pos) = gen(
+                varint_prefixed(all_once(words.iter().map(string))),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 6);
+        }
+        assert_eq!(&buf[..6], &b"\x05abcde"[..]);
+
+        // the payload can reserve space in its output
+        let mut buf = [0u8; 8];
+        {
+            let (_, pos) = gen(
+                varint_prefixed(length_prefixed(1, |len| be_u8(len as u8), string("abc"))),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 5);
+        }
+        assert_eq!(&buf[..5], &b"\x04\x03abc"[..]);
+
+        let mut buf = [0u8; 4];
+        match gen(varint_prefixed(string("abcd")), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(1)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_protobuf() {
+        use crate::bytes::leb128_u64;
+
+        // message { 1: 150, 16: 1, 3: { 1: 150 } }
+        let mut buf = [0u8; 16];
+        {
+            let (_, pos) = gen(
+                tuple((
+                    pb_tag(1, 0),
+                    leb128_u64(150),
+                    pb_tag(16, 0),
+                    leb128_u64(1),
+                    pb_length_delimited(3, pair(pb_tag(1, 0), leb128_u64(150))),
+                )),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 11);
+        }
+        assert_eq!(
+            &buf[..11],
+            &[0x08, 0x96, 0x01, 0x80, 0x01, 0x01, 0x1a, 0x03, 0x08, 0x96, 0x01]
+        );
+
+        // message { 4: { 5: { 1: "ab" }, 6: "cd" } }, with the length of field 6 written by
+        // `length_prefixed`
+        let mut buf = [0u8; 16];
+        {
+            let (_, pos) = gen(
+                pb_length_delimited(
+                    4,
+                    tuple((
+                        pb_length_delimited(5, pb_length_delimited(1, string("ab"))),
+                        pb_tag(6, 2),
+                        length_prefixed(1, |len| be_u8(len as u8), string("cd")),
+                    )),
+                ),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 12);
+        }
+        assert_eq!(
+            &buf[..12],
+            &[0x22, 0x0a, 0x2a, 0x04, 0x0a, 0x02, b'a', b'b', 0x32, 0x02, b'c', b'd']
+        );
+
+        for (field_number, wire_type) in [(0, 0), (1 << 29, 0), (1, 8)] {
+            let mut buf = [0u8; 16];
+            match gen(pb_tag(field_number, wire_type), &mut buf[..]) {
+                Err(GenError::InvalidValue) => {}
+                Err(e) => panic!("unexpected error: {:?}", e),
+                Ok(_) => panic!("should have failed"),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_durations() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let d = Duration::from_secs(5);
+        let v = gen_simple(
+            tuple((
+                duration_secs(d),
+                duration_millis(d),
+                duration_micros(d),
+                system_time_unix(UNIX_EPOCH - Duration::from_millis(1500)),
+            )),
+            Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(&v[..8], &5u64.to_be_bytes()[..]);
+        assert_eq!(&v[8..16], &5_000u64.to_be_bytes()[..]);
+        assert_eq!(&v[16..24], &5_000_000u64.to_be_bytes()[..]);
+        assert_eq!(&v[24..], &(-2i64).to_be_bytes()[..]);
+
+        match gen_simple(duration_micros(Duration::MAX), Vec::new()) {
+            Err(GenError::InvalidValue) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_with_trailer() {
+        fn xor<W: Write>(data: &[u8]) -> impl SerializeFn<W> {
+            be_u8(data.iter().fold(0, |acc, b| acc ^ b))
+        }
+
+        let mut buf = [0u8; 8];
+        {
+            let (_, pos) = gen(
+                tuple((
+                    be_u8(0xaa),
+                    with_trailer(tuple((be_u16(0x0102), be_u8(0x04))), xor),
+                    be_u8(0xbb),
+                )),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 6);
+        }
+        assert_eq!(&buf[..6], &[0xaa, 0x01, 0x02, 0x04, 0x07, 0xbb]);
+
+        let mut buf = [0u8; 2];
+        match gen(with_trailer(be_u16(0x0102), xor), &mut buf[..]) {
+            Err(GenError::BufferTooSmall(1)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reserve_hint() {
+        use crate::multi::all;
+
+        let values: Vec<u32> = (0..1000).collect();
+        let check_no_realloc = |out: WriteContext<Vec<u8>>| {
+            let ptr = out.write.as_ptr();
+            let out = all(values.iter().map(|v| be_u32(*v)))(out)?;
+            assert_eq!(out.write.as_ptr(), ptr);
+            Ok(out)
+        };
+
+        let (buf, pos) = gen(reserve_hint(4000, check_no_realloc), Vec::new()).unwrap();
+        assert_eq!(pos, 4000);
+        assert_eq!(&buf[..8], &[0, 0, 0, 0, 0, 0, 0, 1][..]);
+
+        let mut buf = [0u8; 4];
+        let (_, pos) = gen(reserve_hint(4000, be_u32(1)), &mut buf[..]).unwrap();
+        assert_eq!(pos, 4);
+
+        // wrapping writers forward the hint, bounded by their own limits
+        let mut log = Vec::new();
+        let (out, _) = gen(
+            reserve_hint(4000, be_u32(1)),
+            crate::tee(crate::limit(Vec::new(), 100), &mut log),
+        )
+        .unwrap();
+        let (limited, _) = out.into_inner();
+        let limited = limited.into_inner();
+        assert!(limited.capacity() >= 100 && limited.capacity() < 4000);
+        assert!(log.capacity() >= 4000);
+    }
+
+    #[test]
+    fn test_if_fits() {
+        let mut buf = [0u8; 6];
+
+        {
+            let (rest, pos) = gen(
+                tuple((string("abcd"), be_u16(0x0102), if_fits(2, be_u16(0x0304)))),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 6);
+            assert!(rest.is_empty());
+        }
+        assert_eq!(&buf, b"abcd\x01\x02");
+
+        let mut buf = [0u8; 8];
+        {
+            let cursor = crate::lib::std::io::Cursor::new(&mut buf[..]);
+            let (_, pos) = gen(
+                tuple((string("abcd"), if_fits(4, be_u32(0x0102_0304)))),
+                cursor,
+            )
+            .unwrap();
+            assert_eq!(pos, 8);
+        }
+        assert_eq!(&buf, b"abcd\x01\x02\x03\x04");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_ntp_timestamp() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        // 2000-01-01T00:00:00Z is 3155673600 in NTP time
+        let t = UNIX_EPOCH + Duration::from_secs(946_684_800) + Duration::from_micros(250_000);
+        let v = gen_simple(ntp_timestamp(t), Vec::new()).unwrap();
+        assert_eq!(&v[..4], &3_155_673_600u32.to_be_bytes()[..]);
+        assert_eq!(&v[4..], &0x4000_0000u32.to_be_bytes()[..]);
+
+        // 1900-01-01 itself is the NTP epoch
+        let t = UNIX_EPOCH - Duration::from_secs(2_208_988_800);
+        let v = gen_simple(ntp_timestamp(t), Vec::new()).unwrap();
+        assert_eq!(&v[..], &[0u8; 8][..]);
+
+        match gen_simple(ntp_timestamp(t - Duration::from_secs(1)), Vec::new()) {
+            Err(GenError::InvalidValue) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_json_string_body() {
+        let mut buf = [0u8; 32];
+
+        let len = {
+            let (_, pos) = gen(json_string_body("\"\\/\u{1}\t\u{1f}é"), &mut buf[..]).unwrap();
+            pos as usize
+        };
+
+        assert_eq!(&buf[..len], r#"\"\\/\u0001\t\u001fé"#.as_bytes());
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut buf = [0u8; 6];
+
+        {
+            let fields = [(0b101, 3), (0x1ff, 9), (0b1, 1), (0xffff_ffff, 32)];
+            let (_, pos) = gen(bits(&fields[..]), &mut buf[..]).unwrap();
+            assert_eq!(pos, 6);
+        }
+
+        // 101 111111111 1 followed by 32 set bits, then 3 bits of padding
+        assert_eq!(&buf, &[0xbf, 0xff, 0xff, 0xff, 0xff, 0xf8]);
+
+        let mut buf = [0u8; 2];
+        match gen(bits(&[(0b1111_1111, 8), (0b100, 2)]), &mut buf[..]) {
+            Err(GenError::InvalidValue) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+        assert_eq!(&buf, &[0, 0]);
+
+        match gen(bits(&[(0, 33)]), &mut buf[..]) {
+            Err(GenError::InvalidValue) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_inspect() {
+        let plain = gen_simple(
+            tuple((string("ab"), be_u16(0x0102), string("cd"))),
+            Vec::new(),
+        )
+        .unwrap();
+        let inspected = gen_simple(
+            tuple((
+                string("ab"),
+                inspect("number", be_u16(0x0102)),
+                string("cd"),
+            )),
+            Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(plain, inspected);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stream_keeps_failed() {
+        let mut buf = [0u8; 3];
+        let mut buf2 = [0u8; 2];
+        {
+            let s = Stream::new();
+            s.push(string("ab"));
+            s.push(string("cd"));
+
+            match gen(stream(&s), &mut buf[..]) {
+                Err(GenError::BufferTooSmall(1)) => {}
+                Err(e) => panic!("unexpected error: {:?}", e),
+                Ok(_) => panic!("should have failed"),
+            }
+            assert_eq!(s.len(), 1);
+
+            let (_, pos) = gen(stream(&s), &mut buf2[..]).unwrap();
+            assert_eq!(pos, 2);
+            assert!(s.is_empty());
+        }
+        assert_eq!(&buf2, b"cd");
+    }
 }
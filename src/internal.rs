@@ -19,6 +19,10 @@ pub enum GenError {
     BufferTooBig(usize),
     /// Operation asked for accessing an invalid index
     InvalidOffset,
+    /// The value cannot be represented by the serializer
+    InvalidValue,
+    /// A `core::fmt` formatting trait implementation returned an error
+    FormatError,
     /// IoError returned by Write
     IoError(io::Error),
 
@@ -28,19 +32,65 @@ pub enum GenError {
     NotYetImplemented,
 }
 
+#[cfg(feature = "std")]
+impl GenError {
+    /// Returns the underlying `io::Error` if this is a `GenError::IoError`
+    pub fn as_io_error(&self) -> Option<&io::Error> {
+        match self {
+            GenError::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying `io::Error` if this is a `GenError::IoError`, or the error itself
+    /// otherwise
+    pub fn into_io_error(self) -> Result<io::Error, GenError> {
+        match self {
+            GenError::IoError(err) => Ok(err),
+            err => Err(err),
+        }
+    }
+}
+
 impl fmt::Display for GenError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            GenError::BufferTooSmall(n) => write!(f, "buffer too small, needs {} more bytes", n),
+            GenError::BufferTooBig(n) => {
+                write!(f, "expected to fill the buffer, {} bytes left over", n)
+            }
+            GenError::InvalidOffset => write!(f, "invalid offset"),
+            GenError::InvalidValue => write!(f, "invalid value for field"),
+            GenError::FormatError => write!(f, "formatting error"),
+            GenError::IoError(err) => write!(f, "I/O error: {}", err),
+            GenError::CustomError(code) => write!(f, "custom error {}", code),
+            GenError::NotYetImplemented => write!(f, "not yet implemented"),
+        }
     }
 }
 
 #[cfg(feature = "std")]
 impl std::error::Error for GenError {}
 
+impl From<fmt::Error> for GenError {
+    fn from(_: fmt::Error) -> Self {
+        GenError::FormatError
+    }
+}
+
 impl From<io::Error> for GenError {
+    #[cfg(feature = "std")]
     fn from(err: io::Error) -> Self {
         GenError::IoError(err)
     }
+
+    #[cfg(not(feature = "std"))]
+    fn from(err: io::Error) -> Self {
+        match err {
+            io::Error::InvalidSeek => GenError::InvalidOffset,
+            err => GenError::IoError(err),
+        }
+    }
 }
 
 /// Trait for serializing functions
@@ -48,7 +98,9 @@ impl From<io::Error> for GenError {
 /// Serializing functions take one input `W` that is the target of writing and return an instance
 /// of `cookie_factory::GenResult`.
 ///
-/// This trait is implemented for all `Fn(W) -> GenResult<W>`.
+/// This trait is implemented for all `Fn(W) -> GenResult<W>`. Since `&F` implements `Fn` when
+/// `F` does, a reference to a serializer is also a serializer, and can be passed to combinators
+/// to reuse the serializer without cloning it.
 pub trait SerializeFn<W>: Fn(WriteContext<W>) -> GenResult<W> {}
 
 impl<W, F: Fn(WriteContext<W>) -> GenResult<W>> SerializeFn<W> for F {}
@@ -56,6 +108,7 @@ impl<W, F: Fn(WriteContext<W>) -> GenResult<W>> SerializeFn<W> for F {}
 /// Context around a `Write` impl that is passed through serializing functions
 ///
 /// Currently this only keeps track of the current write position since the start of serialization.
+#[derive(Debug)]
 pub struct WriteContext<W> {
     pub write: W,
     pub position: u64,
@@ -68,10 +121,32 @@ impl<W: Write> From<W> for WriteContext<W> {
 }
 
 impl<W: Write> WriteContext<W> {
+    /// Creates a `WriteContext` starting at the logical position `position`
+    ///
+    /// `WriteContext::from` and the `gen` functions start at position 0. This is useful to
+    /// continue serializing into an output that already contains `position` bytes, while
+    /// keeping positions relative to the start of that output.
+    pub fn new(write: W, position: u64) -> Self {
+        Self { write, position }
+    }
+
+    /// Returns the current position
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
     /// Returns the contained `Write` and the current position
     pub fn into_inner(self) -> (W, u64) {
         (self.write, self.position)
     }
+
+    /// Transforms the contained `Write` with `f`, keeping the current position
+    pub fn map_write<W2, Fm: FnOnce(W) -> W2>(self, f: Fm) -> WriteContext<W2> {
+        WriteContext {
+            write: f(self.write),
+            position: self.position,
+        }
+    }
 }
 
 impl<W: Write> Write for WriteContext<W> {
@@ -81,7 +156,6 @@ impl<W: Write> Write for WriteContext<W> {
         Ok(amt)
     }
 
-    #[cfg(feature = "std")]
     fn flush(&mut self) -> io::Result<()> {
         self.write.flush()
     }
@@ -100,6 +174,262 @@ impl<W: Seek> io::Seek for WriteContext<W> {
     }
 }
 
+/// `Write` impl duplicating the data written to two `Write` impls
+///
+/// Data is first written to the first `Write`, then the part it accepted is written in full to
+/// the second one, and the number of bytes accepted by the first `Write` is returned. If the
+/// second `Write` cannot take all of it, an `io::Error` is returned, so both `Write` impls never
+/// silently diverge.
+pub struct Tee<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Write, B: Write> Tee<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Tee { first, second }
+    }
+
+    /// Returns the contained `Write` impls
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+/// Creates a `Tee` writing to both `a` and `b`
+pub fn tee<A: Write, B: Write>(a: A, b: B) -> Tee<A, B> {
+    Tee::new(a, b)
+}
+
+impl<A: Write, B: Write> Write for Tee<A, B> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let amt = self.first.write(data)?;
+        self.second.write_all(&data[..amt])?;
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.first.flush()?;
+        self.second.flush()
+    }
+}
+
+/// `Write` impl accepting at most a fixed number of bytes
+///
+/// Once the budget is exhausted, writes return 0 bytes, which makes serializers fail with
+/// `GenError::BufferTooSmall`.
+pub struct LimitWriter<W> {
+    inner: W,
+    remaining: usize,
+}
+
+impl<W: Write> LimitWriter<W> {
+    pub fn new(inner: W, max: usize) -> Self {
+        LimitWriter {
+            inner,
+            remaining: max,
+        }
+    }
+
+    /// Returns the number of bytes that can still be written
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns the contained `Write`
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Creates a `LimitWriter` accepting at most `max` bytes
+pub fn limit<W: Write>(w: W, max: usize) -> LimitWriter<W> {
+    LimitWriter::new(w, max)
+}
+
+/// Runs the given serializer `f` with the `Write` impl `w`, aborting once `max_bytes` are written
+///
+/// This guards against serializers writing an unbounded amount of data, for example from a deeply
+/// nested structure built from untrusted input. Going over the limit returns
+/// `GenError::BufferTooSmall`.
+///
+/// ```rust
+/// use cookie_factory::{gen_limited, GenError, combinator::string, multi::all};
+///
+/// let mut buf = [0u8; 1000];
+///
+/// let words = ["abcd"; 100];
+/// {
+///   let (buf, pos) = gen_limited(all(words[..2].iter().map(string)), &mut buf[..], 16).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 1000 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &b"abcdabcd"[..]);
+///
+/// let res = gen_limited(all(words.iter().map(string)), &mut buf[..], 16);
+/// assert!(matches!(res, Err(GenError::BufferTooSmall(4))));
+/// ```
+pub fn gen_limited<W: Write, F: SerializeFn<LimitWriter<W>>>(
+    f: F,
+    w: W,
+    max_bytes: u64,
+) -> Result<(W, u64), GenError> {
+    let max = if max_bytes > usize::MAX as u64 {
+        usize::MAX
+    } else {
+        max_bytes as usize
+    };
+    gen(f, limit(w, max)).map(|(w, position)| (w.into_inner(), position))
+}
+
+impl<W: Write> Write for LimitWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let len = crate::lib::std::cmp::min(data.len(), self.remaining);
+        let amt = self.inner.write(&data[..len])?;
+        self.remaining -= amt;
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// `Write` impl storing the data in a fixed-size ring buffer
+///
+/// Once the buffer is full, writes are short or return 0 bytes, which makes serializers fail with
+/// `GenError::BufferTooSmall`, until some data is read back with `drain`. This bounds the memory
+/// used when streaming to a slow consumer.
+pub struct RingBufferWriter<const N: usize> {
+    buf: [u8; N],
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> RingBufferWriter<N> {
+    pub fn new() -> Self {
+        RingBufferWriter {
+            buf: [0u8; N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of bytes waiting to be drained
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes that can be written before the buffer is full
+    pub fn available(&self) -> usize {
+        N - self.len
+    }
+
+    /// Removes and returns the written bytes, oldest first
+    ///
+    /// Bytes not consumed from the iterator stay in the buffer.
+    pub fn drain(&mut self) -> impl Iterator<Item = u8> + '_ {
+        crate::lib::std::iter::from_fn(move || {
+            if self.len == 0 {
+                return None;
+            }
+            let byte = self.buf[self.start];
+            self.start = (self.start + 1) % N;
+            self.len -= 1;
+            Some(byte)
+        })
+    }
+}
+
+impl<const N: usize> Default for RingBufferWriter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Write for RingBufferWriter<N> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let amt = crate::lib::std::cmp::min(data.len(), self.available());
+        for (i, byte) in data[..amt].iter().enumerate() {
+            self.buf[(self.start + self.len + i) % N] = *byte;
+        }
+        self.len += amt;
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `Write` impl counting the bytes written through it
+///
+/// A `LengthCounter` always owns a `Write` impl, and either forwards the data to it, or only
+/// counts and discards the data, leaving it untouched. Since both cases have the same type, a
+/// serializer built for a `LengthCounter` can be run once to measure its output, then again to
+/// write it.
+pub struct LengthCounter<W> {
+    inner: W,
+    forward: bool,
+    written: u64,
+}
+
+impl<W: Write> LengthCounter<W> {
+    /// Creates a `LengthCounter` forwarding the data to `inner`
+    pub fn new(inner: W) -> Self {
+        LengthCounter {
+            inner,
+            forward: true,
+            written: 0,
+        }
+    }
+
+    /// Creates a `LengthCounter` accepting and discarding all the data, without writing to
+    /// `inner`
+    pub fn discarding(inner: W) -> Self {
+        LengthCounter {
+            inner,
+            forward: false,
+            written: 0,
+        }
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Returns the contained `Write`
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for LengthCounter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let amt = if self.forward {
+            self.inner.write(data)?
+        } else {
+            data.len()
+        };
+        self.written += amt as u64;
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.forward {
+            self.inner.flush()
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Runs the given serializer `f` with the `Write` impl `w` and returns the result
 ///
 /// This internally wraps `w` in a `WriteContext`, starting at position 0.
@@ -141,6 +471,355 @@ pub fn gen_simple<W: Write, F: SerializeFn<W>>(f: F, w: W) -> Result<W, GenError
     f(WriteContext::from(w)).map(|ctx| ctx.into_inner().0)
 }
 
+/// Runs the given slice serializer `f` on the byte slice `buf`, and returns the written part of
+/// the slice along with the remaining part
+///
+/// `f` is run on a reborrow of `buf`, so it must work with slices of any lifetime, like the
+/// serializers accepted by `gen_growing`.
+///
+/// ```rust
+/// use cookie_factory::{gen_slice_out, WriteContext, sequence::pair, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let (written, rest) = gen_slice_out(
+///   |out: WriteContext<&mut [u8]>| pair(string("abcd"), string("efgh"))(out),
+///   &mut buf[..],
+/// ).unwrap();
+/// assert_eq!(written, &b"abcdefgh"[..]);
+/// assert_eq!(rest.len(), 100 - 8);
+/// ```
+pub fn gen_slice_out<F>(f: F, buf: &mut [u8]) -> Result<(&[u8], &mut [u8]), GenError>
+where
+    F: for<'a> SerializeFn<&'a mut [u8]>,
+{
+    let (_, pos) = gen(f, &mut *buf)?;
+    let (written, rest) = buf.split_at_mut(pos as usize);
+    Ok((written, rest))
+}
+
+/// Runs the given serializer `f` with an empty `Vec<u8>` and returns it
+///
+/// ```rust
+/// use cookie_factory::{gen_to_vec, sequence::pair, combinator::string};
+///
+/// let v = gen_to_vec(pair(string("Hello "), string("World!"))).unwrap();
+///
+/// assert_eq!(&v[..], &b"Hello World!"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn gen_to_vec<F: SerializeFn<Vec<u8>>>(f: F) -> Result<Vec<u8>, GenError> {
+    gen_simple(f, Vec::new())
+}
+
+/// Runs the given serializer `f` with an empty `Vec<u8>` of capacity `cap` and returns it
+///
+/// ```rust
+/// use cookie_factory::{gen_to_vec_with_capacity, combinator::string};
+///
+/// let v = gen_to_vec_with_capacity(64, string("Hello World!")).unwrap();
+///
+/// assert_eq!(&v[..], &b"Hello World!"[..]);
+/// assert!(v.capacity() >= 64);
+/// ```
+#[cfg(feature = "std")]
+pub fn gen_to_vec_with_capacity<F: SerializeFn<Vec<u8>>>(
+    cap: usize,
+    f: F,
+) -> Result<Vec<u8>, GenError> {
+    gen_simple(f, Vec::with_capacity(cap))
+}
+
+/// Lazily serializes each item into its own `Vec<u8>`
+///
+/// Each item is turned into a serializer by `generator` only when the iterator is advanced, so
+/// a large collection can be written out chunk by chunk without building the whole output.
+///
+/// ```rust
+/// use cookie_factory::{gen_iter, bytes::be_u16};
+///
+/// let mut chunks = gen_iter(vec![1u16, 2], be_u16);
+///
+/// assert_eq!(chunks.next().unwrap().unwrap(), vec![0, 1]);
+/// assert_eq!(chunks.next().unwrap().unwrap(), vec![0, 2]);
+/// assert!(chunks.next().is_none());
+/// ```
+#[cfg(feature = "std")]
+pub fn gen_iter<E, I, F, G>(
+    items: I,
+    generator: F,
+) -> impl Iterator<Item = Result<Vec<u8>, GenError>>
+where
+    I: IntoIterator<Item = E>,
+    F: Fn(E) -> G,
+    G: SerializeFn<Vec<u8>>,
+{
+    items
+        .into_iter()
+        .map(move |item| gen_simple(generator(item), Vec::new()))
+}
+
+/// Runs the given serializer `f`, appending to `buf`, and returns the number of bytes written
+///
+/// Unlike `gen_to_vec`, the buffer is borrowed, so it can be reused across many calls.
+///
+/// ```rust
+/// use cookie_factory::{gen_append, combinator::string, bytes::be_u16};
+///
+/// let mut buf = Vec::new();
+///
+/// assert_eq!(gen_append(string("abcd"), &mut buf).unwrap(), 4);
+/// assert_eq!(gen_append(be_u16(0x0102), &mut buf).unwrap(), 2);
+///
+/// assert_eq!(&buf[..], &b"abcd\x01\x02"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn gen_append<'a, F: SerializeFn<&'a mut Vec<u8>>>(
+    f: F,
+    buf: &'a mut Vec<u8>,
+) -> Result<u64, GenError> {
+    gen(f, buf).map(|(_, position)| position)
+}
+
+/// Runs the given serializer `f` with the `Write` impl `w`, checking first that the whole output
+/// fits
+///
+/// The serializer is first run with a discarding `LengthCounter`. If the output needs more bytes
+/// than `w` has left, `GenError::BufferTooSmall` reports the total number of missing bytes, while
+/// `gen` only reports the shortfall of the first write that did not fit. This is the
+/// recommended way to size a buffer before retrying. Nothing is written to `w` in that case.
+///
+/// Since the serializer is run twice, it must produce the same output each time: serializers
+/// consuming their input, such as `stream` or `all_once`, cannot be used here.
+///
+/// ```rust
+/// use cookie_factory::{gen_measuring, GenError, sequence::tuple, combinator::string};
+///
+/// let mut buf = [0u8; 6];
+///
+/// match gen_measuring(tuple((string("abcd"), string("efgh"), string("ijkl"))), &mut buf[..]) {
+///   Err(GenError::BufferTooSmall(missing)) => assert_eq!(missing, 6),
+///   _ => panic!("the output should not fit"),
+/// }
+/// ```
+pub fn gen_measuring<W: Write, F: SerializeFn<LengthCounter<W>>>(
+    f: F,
+    w: W,
+) -> Result<(W, u64), GenError>
+where
+    WriteContext<W>: RemainingCapacity,
+{
+    let (counter, _) = f(WriteContext::from(LengthCounter::discarding(w)))?.into_inner();
+    let needed = counter.written();
+    let out = WriteContext::from(counter.into_inner());
+
+    if let Some(remaining) = out.remaining() {
+        if (remaining as u64) < needed {
+            return Err(GenError::BufferTooSmall(
+                (needed - remaining as u64) as usize,
+            ));
+        }
+    }
+
+    let (counter, position) = f(out.map_write(LengthCounter::new))?.into_inner();
+    Ok((counter.into_inner(), position))
+}
+
+/// Runs the given slice serializer `f` against a growing `Vec<u8>` until it succeeds, and
+/// returns the written data
+///
+/// The buffer starts small and is grown each time `f` fails with `GenError::BufferTooSmall`,
+/// then `f` is run again from the start. The buffer at least doubles each time, as the missing
+/// amount reported by the failing serializer may only cover a small part of the output. Other
+/// errors are returned as is.
+///
+/// ```rust
+/// use cookie_factory::{gen_growing, WriteContext, combinator::string, multi::all};
+///
+/// let words = vec!["abcd"; 50];
+/// let v = gen_growing(|out: WriteContext<&mut [u8]>| all(words.iter().map(string))(out)).unwrap();
+///
+/// assert_eq!(v.len(), 200);
+/// assert_eq!(&v[..8], &b"abcdabcd"[..]);
+/// ```
+#[cfg(feature = "std")]
+pub fn gen_growing<F>(f: F) -> Result<Vec<u8>, GenError>
+where
+    F: for<'a> SerializeFn<&'a mut [u8]>,
+{
+    let mut buf = vec![0u8; 64];
+    loop {
+        match gen_ref(&f, &mut buf[..]) {
+            Ok((_, position)) => {
+                buf.truncate(position as usize);
+                return Ok(buf);
+            }
+            Err(GenError::BufferTooSmall(missing)) => {
+                let len = buf.len() + missing.max(buf.len());
+                buf.resize(len, 0);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Runs the serializer `f` by reference with the `Write` impl `w` and returns the result
+///
+/// This allows building a serializer once and running it multiple times.
+///
+/// ```rust
+/// use cookie_factory::{gen_ref, combinator::string};
+///
+/// let mut buf1 = [0u8; 4];
+/// let mut buf2 = [0u8; 100];
+///
+/// {
+///   let serializer = string("abcd");
+///   let (_, pos1) = gen_ref(&serializer, &mut buf1[..]).unwrap();
+///   let (buf2, pos2) = gen_ref(&serializer, &mut buf2[..]).unwrap();
+///   assert_eq!((pos1, pos2), (4, 4));
+///   assert_eq!(buf2.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf1[..], &b"abcd"[..]);
+/// assert_eq!(&buf2[..4], &b"abcd"[..]);
+/// ```
+pub fn gen_ref<W: Write, F: SerializeFn<W>>(f: &F, w: W) -> Result<(W, u64), GenError> {
+    f(WriteContext::from(w)).map(|ctx| ctx.into_inner())
+}
+
+/// Runs the serializer `f` by reference with the `Write` impl `w` and returns the updated `w`
+///
+/// ```rust
+/// use cookie_factory::{gen_simple_ref, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let serializer = string("abcd");
+///   let rest = gen_simple_ref(&serializer, &mut buf[..]).unwrap();
+///   let rest = gen_simple_ref(&serializer, rest).unwrap();
+///   assert_eq!(rest.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &b"abcdabcd"[..]);
+/// ```
+pub fn gen_simple_ref<W: Write, F: SerializeFn<W>>(f: &F, w: W) -> Result<W, GenError> {
+    f(WriteContext::from(w)).map(|ctx| ctx.into_inner().0)
+}
+
+/// Named positions recorded during serialization
+///
+/// This is a shared handle: clones refer to the same set of positions. Positions are recorded
+/// with the `combinator::record_offset` serializer, usually through `gen_with_offsets`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct Checkpoints(
+    std::rc::Rc<std::cell::RefCell<std::collections::BTreeMap<&'static str, u64>>>,
+);
+
+#[cfg(feature = "std")]
+impl Checkpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `position` under `name`, replacing any previous value
+    pub fn record(&self, name: &'static str, position: u64) {
+        self.0.borrow_mut().insert(name, position);
+    }
+
+    /// Returns the position recorded under `name`
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.0.borrow().get(name).copied()
+    }
+
+    /// Returns a copy of all the recorded positions
+    pub fn to_map(&self) -> std::collections::BTreeMap<&'static str, u64> {
+        self.0.borrow().clone()
+    }
+}
+
+/// Runs the serializer built by `build` with the `Write` impl `w`, and returns the positions
+/// recorded by the serializer along with the `Write` impl and the final position
+///
+/// `build` receives the `Checkpoints` collector that should be passed to `record_offset`.
+///
+/// ```rust
+/// use cookie_factory::{gen_with_offsets, sequence::tuple, combinator::{record_offset, string}};
+///
+/// let (buf, pos, checkpoints) = gen_with_offsets(
+///     |c| tuple((string("ab"), record_offset(c, "start"), string("cde"), record_offset(c, "end"))),
+///     Vec::new(),
+/// ).unwrap();
+///
+/// assert_eq!(&buf[..], &b"abcde"[..]);
+/// assert_eq!(pos, 5);
+/// assert_eq!(checkpoints.get("start"), Some(2));
+/// assert_eq!(checkpoints.get("end"), Some(5));
+/// ```
+#[cfg(feature = "std")]
+pub fn gen_with_offsets<W: Write, F: SerializeFn<W>>(
+    build: impl Fn(&Checkpoints) -> F,
+    w: W,
+) -> Result<(W, u64, Checkpoints), GenError> {
+    let checkpoints = Checkpoints::new();
+    let (w, position) = gen(build(&checkpoints), w)?;
+    Ok((w, position, checkpoints))
+}
+
+/// `Write` impl counting the bytes accepted by the wrapped `Write` impl
+///
+/// The count is shared with the caller of `gen_partial`, so it is still available when the
+/// serializer fails and the writer is dropped.
+#[cfg(feature = "std")]
+pub struct PartialWriter<W> {
+    inner: W,
+    written: std::rc::Rc<std::cell::Cell<u64>>,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for PartialWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let amt = self.inner.write(data)?;
+        self.written.set(self.written.get() + amt as u64);
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Runs the given serializer `f` with the `Write` impl `w`, and returns the result along with
+/// the number of bytes written, even if the serializer failed
+///
+/// `w` is wrapped in a `PartialWriter` for the duration of the serialization, so `f` must be
+/// generic over the `Write` impl.
+///
+/// ```rust
+/// use cookie_factory::{gen_partial, GenError, sequence::tuple, combinator::string};
+///
+/// let mut buf = [0u8; 6];
+///
+/// let (res, written) = gen_partial(tuple((string("abcd"), string("efgh"))), &mut buf[..]);
+/// assert!(matches!(res, Err(GenError::BufferTooSmall(2))));
+/// assert_eq!(written, 6);
+/// ```
+#[cfg(feature = "std")]
+pub fn gen_partial<W: Write, F: SerializeFn<PartialWriter<W>>>(
+    f: F,
+    w: W,
+) -> (Result<(W, u64), GenError>, u64) {
+    let written = std::rc::Rc::new(std::cell::Cell::new(0));
+    let w = PartialWriter {
+        inner: w,
+        written: written.clone(),
+    };
+    let res = gen(f, w).map(|(w, position)| (w.inner, position));
+    (res, written.get())
+}
+
 /// Trait for `Write` types that allow skipping over the data
 pub trait Skip: Write {
     fn skip(s: WriteContext<Self>, sz: usize) -> GenResult<Self>
@@ -148,6 +827,23 @@ pub trait Skip: Write {
         Self: Sized;
 }
 
+/// Trait for writing contexts that can tell how many bytes can still be written
+pub trait RemainingCapacity {
+    /// Returns the number of bytes left, or `None` if the writer is unbounded
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Trait for `Write` types that can allocate space ahead of writing
+///
+/// Writers that cannot grow keep the no-op default, with an empty impl such as
+/// `impl Reserve for MyWriter {}`. Wrapping writers forward the hint to the writer they wrap.
+pub trait Reserve: Write {
+    /// Makes room for at least `additional` more bytes, if the writer can grow
+    fn reserve(&mut self, _additional: usize) {}
+}
+
 /// Trait for `Write` types that allow skipping and reserving a slice, then writing some data,
 /// then write something in the slice we reserved using the return for our data write.
 pub trait BackToTheBuffer: Write {
@@ -226,6 +922,29 @@ impl Skip for io::Cursor<&mut [u8]> {
     }
 }
 
+#[cfg(feature = "std")]
+impl Skip for Vec<u8> {
+    fn skip(mut s: WriteContext<Self>, len: usize) -> GenResult<Self> {
+        let new_len = s.write.len() + len;
+        s.write.resize(new_len, 0);
+        s.position += len as u64;
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Skip for io::Cursor<Vec<u8>> {
+    fn skip(mut s: WriteContext<Self>, len: usize) -> GenResult<Self> {
+        let cursor_position = s.write.position() + len as u64;
+        if cursor_position > s.write.get_ref().len() as u64 {
+            s.write.get_mut().resize(cursor_position as usize, 0);
+        }
+        s.write.set_position(cursor_position);
+        s.position += len as u64;
+        Ok(s)
+    }
+}
+
 impl BackToTheBuffer for &mut [u8] {
     fn reserve_write_use<
         Tmp,
@@ -282,7 +1001,7 @@ impl BackToTheBuffer for Vec<u8> {
         } = s;
 
         let start_len = vec.len();
-        vec.extend(std::iter::repeat(0).take(reserved));
+        vec.resize(start_len + reserved, 0);
 
         let (mut new_context, tmp) = gen(WriteContext {
             write: vec,
@@ -298,16 +1017,641 @@ impl BackToTheBuffer for Vec<u8> {
         )?;
 
         let tmp_written = tmp_context.write.len();
-        if tmp_written != reserved {
+        if tmp_written < reserved {
             return Err(GenError::BufferTooBig(reserved - tmp_written));
+        } else if tmp_written > reserved {
+            return Err(GenError::BufferTooSmall(tmp_written - reserved));
         }
 
+        // `gen` owns the vector and could have truncated it below the reserved region
+        let target = new_context
+            .write
+            .get_mut(start_len..(start_len + reserved))
+            .ok_or(GenError::InvalidOffset)?;
+
         // FIXME?: find a way to do that without copying
         // Vec::from_raw_parts + core::mem::forget makes it work, but
         // if `before` writes more than `reserved`, realloc will cause troubles
-        new_context.write[start_len..(start_len + reserved)]
-            .copy_from_slice(&tmp_context.write[..]);
+        target.copy_from_slice(&tmp_context.write[..]);
 
         Ok(new_context)
     }
 }
+
+/// Skips by appending zeroes to the `BytesMut` wrapped by the `Writer`
+#[cfg(feature = "bytes")]
+impl Skip for ::bytes::buf::Writer<::bytes::BytesMut> {
+    fn skip(mut s: WriteContext<Self>, len: usize) -> GenResult<Self> {
+        let bytes = s.write.get_mut();
+        bytes.resize(bytes.len() + len, 0);
+        s.position += len as u64;
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl BackToTheBuffer for ::bytes::buf::Writer<::bytes::BytesMut> {
+    fn reserve_write_use<
+        Tmp,
+        Gen: Fn(WriteContext<Self>) -> Result<(WriteContext<Self>, Tmp), GenError>,
+        Before: Fn(WriteContext<Self>, Tmp) -> GenResult<Self>,
+    >(
+        s: WriteContext<Self>,
+        reserved: usize,
+        gen: &Gen,
+        before: &Before,
+    ) -> Result<WriteContext<Self>, GenError> {
+        use ::bytes::BufMut;
+
+        let start = s.write.get_ref().len();
+        let s = Self::skip(s, reserved)?;
+        let original_position = s.position - reserved as u64;
+
+        let (mut new_context, tmp) = gen(s)?;
+
+        let tmp_context = before(
+            WriteContext {
+                write: ::bytes::BytesMut::new().writer(),
+                position: original_position,
+            },
+            tmp,
+        )?;
+
+        let tmp_written = tmp_context.write.get_ref().len();
+        if tmp_written < reserved {
+            return Err(GenError::BufferTooBig(reserved - tmp_written));
+        } else if tmp_written > reserved {
+            return Err(GenError::BufferTooSmall(tmp_written - reserved));
+        }
+
+        new_context
+            .write
+            .get_mut()
+            .get_mut(start..(start + reserved))
+            .ok_or(GenError::InvalidOffset)?
+            .copy_from_slice(tmp_context.write.get_ref());
+
+        Ok(new_context)
+    }
+}
+
+impl RemainingCapacity for WriteContext<&mut [u8]> {
+    fn remaining(&self) -> Option<usize> {
+        Some(self.write.len())
+    }
+}
+
+impl RemainingCapacity for WriteContext<io::Cursor<&mut [u8]>> {
+    fn remaining(&self) -> Option<usize> {
+        Some(
+            self.write
+                .get_ref()
+                .len()
+                .saturating_sub(self.write.position() as usize),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl RemainingCapacity for WriteContext<Vec<u8>> {}
+
+#[cfg(feature = "std")]
+impl RemainingCapacity for WriteContext<io::Cursor<Vec<u8>>> {}
+
+impl Reserve for &mut [u8] {}
+
+impl Reserve for io::Cursor<&mut [u8]> {}
+
+#[cfg(feature = "std")]
+impl Reserve for Vec<u8> {
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
+}
+
+impl<W: Reserve> Reserve for &mut W {
+    fn reserve(&mut self, additional: usize) {
+        (**self).reserve(additional)
+    }
+}
+
+impl<W: Reserve> Reserve for WriteContext<W> {
+    fn reserve(&mut self, additional: usize) {
+        self.write.reserve(additional)
+    }
+}
+
+impl<A: Reserve, B: Reserve> Reserve for Tee<A, B> {
+    fn reserve(&mut self, additional: usize) {
+        self.first.reserve(additional);
+        self.second.reserve(additional);
+    }
+}
+
+impl<W: Reserve> Reserve for LimitWriter<W> {
+    fn reserve(&mut self, additional: usize) {
+        let additional = crate::lib::std::cmp::min(additional, self.remaining);
+        self.inner.reserve(additional)
+    }
+}
+
+impl<const N: usize> Reserve for RingBufferWriter<N> {}
+
+impl<W: Reserve> Reserve for LengthCounter<W> {
+    fn reserve(&mut self, additional: usize) {
+        if self.forward {
+            self.inner.reserve(additional)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Reserve> Reserve for PartialWriter<W> {
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Reserve for io::Cursor<Vec<u8>> {
+    fn reserve(&mut self, additional: usize) {
+        let needed = (self.position() as usize).saturating_sub(self.get_ref().len()) + additional;
+        self.get_mut().reserve(needed)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl RemainingCapacity for WriteContext<::bytes::buf::Writer<::bytes::BytesMut>> {}
+
+#[cfg(feature = "bytes")]
+impl Reserve for ::bytes::buf::Writer<::bytes::BytesMut> {
+    fn reserve(&mut self, additional: usize) {
+        self.get_mut().reserve(additional)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_fmt_error() {
+        use crate::combinator::string;
+        use std::fmt::Write as _;
+
+        struct Failing;
+
+        impl fmt::Display for Failing {
+            fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        fn formatted<D: fmt::Display>(value: D) -> impl SerializeFn<Vec<u8>> {
+            move |out: WriteContext<Vec<u8>>| {
+                let mut s = String::new();
+                write!(s, "<{}>", value)?;
+                string(s)(out)
+            }
+        }
+
+        assert_eq!(gen_simple(formatted(1), Vec::new()).unwrap(), b"<1>");
+        assert!(matches!(
+            gen_simple(formatted(Failing), Vec::new()),
+            Err(GenError::FormatError)
+        ));
+    }
+
+    #[test]
+    fn test_display() {
+        let cases = vec![
+            (
+                GenError::BufferTooSmall(5),
+                "buffer too small, needs 5 more bytes",
+            ),
+            (
+                GenError::BufferTooBig(3),
+                "expected to fill the buffer, 3 bytes left over",
+            ),
+            (GenError::InvalidOffset, "invalid offset"),
+            (GenError::InvalidValue, "invalid value for field"),
+            (GenError::FormatError, "formatting error"),
+            (
+                GenError::IoError(io::Error::new(
+                    std::io::ErrorKind::WriteZero,
+                    "failed to write",
+                )),
+                "I/O error: failed to write",
+            ),
+            (GenError::CustomError(42), "custom error 42"),
+            (GenError::NotYetImplemented, "not yet implemented"),
+        ];
+
+        for (err, expected) in cases {
+            assert_eq!(err.to_string(), expected);
+        }
+        assert_eq!(
+            format!("{:?}", GenError::BufferTooSmall(5)),
+            "BufferTooSmall(5)"
+        );
+    }
+
+    #[test]
+    fn test_io_error_access() {
+        let err = GenError::IoError(io::Error::new(std::io::ErrorKind::WouldBlock, "not ready"));
+        assert_eq!(
+            err.as_io_error().map(|e| e.kind()),
+            Some(std::io::ErrorKind::WouldBlock)
+        );
+        assert_eq!(
+            err.into_io_error().unwrap().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+
+        let err = GenError::BufferTooSmall(3);
+        assert!(err.as_io_error().is_none());
+        match err.into_io_error() {
+            Err(GenError::BufferTooSmall(3)) => {}
+            _ => panic!("the original error should be returned"),
+        }
+    }
+
+    #[test]
+    fn test_map_write() {
+        use crate::combinator::string;
+
+        let ctx = string("ab")(WriteContext::from(Vec::new())).unwrap();
+        let ctx = ctx.map_write(|v| {
+            let mut cursor = io::Cursor::new(v);
+            cursor.set_position(2);
+            cursor
+        });
+        assert_eq!(ctx.position, 2);
+
+        let ctx = string("cd")(ctx).unwrap();
+        assert_eq!(ctx.position, 4);
+
+        let ctx = ctx.map_write(io::Cursor::into_inner);
+        let ctx = string("ef")(ctx).unwrap();
+        let (v, position) = ctx.into_inner();
+
+        assert_eq!(position, 6);
+        assert_eq!(&v[..], &b"abcdef"[..]);
+    }
+
+    #[test]
+    fn test_debug() {
+        let ctx = WriteContext::new(vec![1u8, 2], 5);
+        assert_eq!(ctx.position(), 5);
+        assert_eq!(
+            format!("{:?}", ctx),
+            "WriteContext { write: [1, 2], position: 5 }"
+        );
+    }
+
+    #[test]
+    fn test_new_position() {
+        use crate::combinator::{checkpoint, string};
+        use crate::sequence::pair;
+
+        let positions = std::cell::RefCell::new(Vec::new());
+        let serializer = pair(
+            checkpoint(string("ab"), |_| {
+                |out: WriteContext<Vec<u8>>| {
+                    positions.borrow_mut().push(out.position);
+                    Ok(out)
+                }
+            }),
+            string("cd"),
+        );
+
+        let out = serializer(WriteContext::new(Vec::new(), 10)).unwrap();
+        let (v, position) = out.into_inner();
+        assert_eq!(&v[..], &b"abcd"[..]);
+        assert_eq!(position, 14);
+        assert_eq!(&positions.borrow()[..], &[12][..]);
+    }
+
+    #[test]
+    fn test_gen_iter() {
+        use crate::combinator::string;
+        use crate::multi::all;
+
+        let words = ["ab", "cde", "", "f"];
+
+        let chunks = gen_iter(words.iter(), string)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 4);
+
+        let whole = gen_simple(all(words.iter().map(string)), Vec::new()).unwrap();
+        assert_eq!(chunks.concat(), whole);
+    }
+
+    #[test]
+    fn test_gen_append() {
+        use crate::combinator::string;
+        use crate::multi::all;
+
+        let mut buf = b"start:".to_vec();
+        let words = ["ab", "cd"];
+
+        assert_eq!(gen_append(string("xyz"), &mut buf).unwrap(), 3);
+        assert_eq!(
+            gen_append(all(words.iter().map(string)), &mut buf).unwrap(),
+            4
+        );
+        assert_eq!(&buf[..], &b"start:xyzabcd"[..]);
+    }
+
+    #[test]
+    fn test_gen_measuring() {
+        use crate::bytes::{be_u16, be_u32};
+        use crate::combinator::string;
+        use crate::multi::all;
+        use crate::sequence::tuple;
+
+        fn serializer<'a, W: Write + 'a>(values: &'a [u32]) -> impl SerializeFn<W> + 'a {
+            tuple((
+                string("header"),
+                be_u16(values.len() as u16),
+                all(values.iter().map(|v| be_u32(*v))),
+            ))
+        }
+        let values = [1u32, 2, 3];
+
+        let expected = gen_to_vec(serializer(&values)).unwrap();
+        assert_eq!(expected.len(), 20);
+
+        let mut small = [0u8; 9];
+        match gen_measuring(serializer(&values), &mut small[..]) {
+            Err(GenError::BufferTooSmall(11)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+        assert_eq!(small, [0u8; 9]);
+
+        let mut buf = [0u8; 20];
+        {
+            let (rest, pos) = gen_measuring(serializer(&values), &mut buf[..]).unwrap();
+            assert_eq!(pos, 20);
+            assert!(rest.is_empty());
+        }
+        assert_eq!(&buf[..], &expected[..]);
+
+        let (v, pos) = gen_measuring(serializer(&values), Vec::new()).unwrap();
+        assert_eq!(pos, 20);
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_ring_buffer_writer() {
+        use crate::combinator::slice;
+
+        let payload: Vec<u8> = (0..100u8).collect();
+        let mut ring = RingBufferWriter::<16>::new();
+        let mut received = Vec::new();
+
+        let mut sent = 0;
+        while sent < payload.len() {
+            let (res, written) = gen_partial(slice(&payload[sent..]), &mut ring);
+            match res {
+                Ok(_) => {}
+                Err(GenError::BufferTooSmall(_)) => assert_eq!(ring.available(), 0),
+                Err(e) => panic!("unexpected error: {:?}", e),
+            }
+            sent += written as usize;
+            assert!(ring.len() <= 16);
+
+            // the consumer only reads part of the data each time
+            received.extend(ring.drain().take(10));
+        }
+        received.extend(ring.drain());
+
+        assert_eq!(received, payload);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_gen_slice_out() {
+        use crate::bytes::be_u16;
+        use crate::combinator::{back_to_the_buffer, string};
+
+        let mut buf = [0u8; 10];
+        let (written, rest) = gen_slice_out(
+            |out: WriteContext<&mut [u8]>| {
+                back_to_the_buffer(
+                    2,
+                    move |out| {
+                        let start = out.position;
+                        let out = string("abcd")(out)?;
+                        let len = out.position - start;
+                        Ok((out, len))
+                    },
+                    move |out, len| be_u16(len as u16)(out),
+                )(out)
+            },
+            &mut buf[..],
+        )
+        .unwrap();
+
+        assert_eq!(written, &b"\x00\x04abcd"[..]);
+        assert_eq!(rest.len(), 4);
+        rest[0] = b'!';
+        assert_eq!(buf[6], b'!');
+
+        // the same serializer can be used with `gen_growing`
+        fn ser(out: WriteContext<&mut [u8]>) -> GenResult<&mut [u8]> {
+            string("abcd")(out)
+        }
+        let mut buf = [0u8; 10];
+        let (written, _) = gen_slice_out(ser, &mut buf[..]).unwrap();
+        assert_eq!(written, &gen_growing(ser).unwrap()[..]);
+
+        let mut buf = [0u8; 2];
+        match gen_slice_out(
+            |out: WriteContext<&mut [u8]>| string("abcd")(out),
+            &mut buf[..],
+        ) {
+            Err(GenError::BufferTooSmall(2)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_tee() {
+        use crate::bytes::be_u16;
+        use crate::combinator::string;
+        use crate::sequence::pair;
+
+        let mut log = Vec::new();
+        let mut buf = [0u8; 6];
+
+        let (out, pos) = gen(
+            pair(string("abcd"), be_u16(0x0102)),
+            tee(&mut buf[..], &mut log),
+        )
+        .unwrap();
+        let (rest, _) = out.into_inner();
+
+        assert_eq!(pos, 6);
+        assert!(rest.is_empty());
+        assert_eq!(&buf[..], &b"abcd\x01\x02"[..]);
+        assert_eq!(&log[..], &buf[..]);
+
+        // the first half is short: the second half gets exactly what the first one accepted
+        let mut log = Vec::new();
+        let mut buf = [0u8; 2];
+        match gen(string("abcd"), tee(&mut buf[..], &mut log)) {
+            Err(GenError::BufferTooSmall(2)) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+        assert_eq!(&buf[..], &b"ab"[..]);
+        assert_eq!(&log[..], &b"ab"[..]);
+
+        // the second half is short: an error is returned instead of diverging
+        match gen(string("abcd"), tee(Vec::new(), limit(Vec::new(), 3))) {
+            Err(GenError::IoError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::WriteZero),
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+    }
+
+    #[test]
+    fn test_gen_partial() {
+        use crate::combinator::string;
+        use crate::sequence::tuple;
+
+        let mut buf = [0u8; 10];
+        let (res, written) = gen_partial(
+            tuple((string("abcd"), string("efgh"), string("ijkl"))),
+            &mut buf[..],
+        );
+        match res {
+            Err(GenError::BufferTooSmall(2)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+        assert_eq!(written, 10);
+
+        let (res, written) = gen_partial(string("abcd"), Vec::new());
+        let (v, pos) = res.unwrap();
+        assert_eq!((pos, written), (4, 4));
+        assert_eq!(&v[..], &b"abcd"[..]);
+    }
+
+    #[test]
+    fn test_gen_ref() {
+        use crate::bytes::be_u16;
+        use crate::combinator::string;
+        use crate::sequence::tuple;
+
+        let mut buf1 = [0u8; 4];
+        let mut buf2 = [0u8; 8];
+
+        {
+            let serializer = tuple((string("ab"), be_u16(0x0102)));
+
+            let (rest1, pos1) = gen_ref(&serializer, &mut buf1[..]).unwrap();
+            assert_eq!(pos1, 4);
+            assert!(rest1.is_empty());
+
+            let (rest2, pos2) = gen_ref(&serializer, &mut buf2[..]).unwrap();
+            assert_eq!(pos2, 4);
+            assert_eq!(rest2.len(), 4);
+        }
+
+        assert_eq!(&buf1, b"ab\x01\x02");
+        assert_eq!(&buf2[..4], b"ab\x01\x02");
+    }
+
+    #[test]
+    fn test_gen_growing() {
+        use crate::bytes::be_u32;
+        use crate::multi::all;
+
+        let values: Vec<u32> = (0..100).collect();
+        let runs = std::cell::Cell::new(0);
+        let v = gen_growing(|out: WriteContext<&mut [u8]>| {
+            runs.set(runs.get() + 1);
+            all(values.iter().map(|v| be_u32(*v)))(out)
+        })
+        .unwrap();
+
+        // 64, 128, 256 then 512 bytes
+        assert_eq!(runs.get(), 4);
+        assert_eq!(v.len(), 400);
+        assert_eq!(&v[..8], &[0, 0, 0, 0, 0, 0, 0, 1][..]);
+        assert_eq!(&v[396..], &[0, 0, 0, 99][..]);
+    }
+
+    #[test]
+    fn test_limit() {
+        use crate::combinator::string;
+        use crate::sequence::pair;
+
+        let (out, pos) = gen(string("abcd"), limit(Vec::new(), 6)).unwrap();
+        assert_eq!(pos, 4);
+        assert_eq!(out.remaining(), 2);
+        assert_eq!(&out.into_inner()[..], &b"abcd"[..]);
+
+        match gen(pair(string("abcd"), string("efgh")), limit(Vec::new(), 6)) {
+            Err(GenError::BufferTooSmall(2)) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+    }
+
+    #[test]
+    fn test_gen_limited() {
+        use crate::combinator::string;
+
+        // a serializer nesting brackets as deep as asked, here deeper than the limit allows
+        fn nested<W: Write>(depth: usize) -> impl SerializeFn<W> {
+            move |mut out: WriteContext<W>| {
+                for _ in 0..depth {
+                    out = string("[")(out)?;
+                }
+                for _ in 0..depth {
+                    out = string("]")(out)?;
+                }
+                Ok(out)
+            }
+        }
+
+        let (out, pos) = gen_limited(nested(3), Vec::new(), 6).unwrap();
+        assert_eq!(pos, 6);
+        assert_eq!(&out[..], &b"[[[]]]"[..]);
+
+        match gen_limited(nested(1000), Vec::new(), 64) {
+            Err(GenError::BufferTooSmall(1)) => {}
+            r => panic!("unexpected result {:?}", r.map(|(_, pos)| pos)),
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_bytes_mut() {
+        use crate::bytes::{be_u16, be_u32};
+        use crate::combinator::{back_to_the_buffer, string};
+        use crate::sequence::tuple;
+        use ::bytes::{BufMut, BytesMut};
+
+        fn serializer<W: BackToTheBuffer>() -> impl SerializeFn<W> {
+            tuple((
+                be_u16(0x0102),
+                back_to_the_buffer(
+                    4,
+                    move |buf| gen(string("test"), buf),
+                    move |buf, len| gen_simple(be_u32(len as u32), buf),
+                ),
+                string("end"),
+            ))
+        }
+
+        let (vec, vec_pos) = gen(serializer(), Vec::new()).unwrap();
+        let (writer, pos) = gen(serializer(), BytesMut::new().writer()).unwrap();
+
+        assert_eq!(pos, vec_pos);
+        assert_eq!(&writer.into_inner()[..], &vec[..]);
+    }
+}
@@ -1,21 +1,75 @@
-// there's no real io error on a byte slice
-pub type Error = ();
+/// Errors returned by the `no_std` `Write` and `Seek` implementations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The writer could not accept any more data
+    WriteZero,
+    /// A seek targeted a negative or overflowing position
+    InvalidSeek,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::WriteZero => write!(f, "failed to write whole buffer"),
+            Error::InvalidSeek => write!(f, "invalid seek to a negative or overflowing position"),
+        }
+    }
+}
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 pub trait Write {
     fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+        while !buf.is_empty() {
+            match self.write(buf)? {
+                0 => return Err(Error::WriteZero),
+                n => buf = &buf[n..],
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Write for &mut [u8] {
     fn write(&mut self, data: &[u8]) -> Result<usize> {
         let amt = core::cmp::min(data.len(), self.len());
-        let (a, b) = core::mem::replace(self, &mut []).split_at_mut(amt);
+        let (a, b) = core::mem::take(self).split_at_mut(amt);
         a.copy_from_slice(&data[..amt]);
         *self = b;
         Ok(amt)
     }
 }
 
+impl<W: Write + ?Sized> Write for &mut W {
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        (**self).write(data)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+}
+
+/// Writes as much as fits in the remaining capacity of the vector
+#[cfg(feature = "heapless")]
+impl<const N: usize> Write for heapless::Vec<u8, N> {
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        let amt = core::cmp::min(data.len(), N - self.len());
+        self.extend_from_slice(&data[..amt])
+            .map_err(|_| Error::WriteZero)?;
+        Ok(amt)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> crate::internal::Reserve for heapless::Vec<u8, N> {}
+
 pub enum SeekFrom {
     Start(u64),
     Current(i64),
@@ -23,6 +77,10 @@ pub enum SeekFrom {
 
 pub trait Seek {
     fn seek(&mut self, pos: SeekFrom) -> Result<u64>;
+
+    fn stream_position(&mut self) -> Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
 }
 
 // Minimal re-implementation of std::io::Cursor so it
@@ -39,7 +97,7 @@ impl<'a> Cursor<&'a mut [u8]> {
     }
 
     pub fn position(&self) -> u64 {
-        self.1 as u64
+        self.1
     }
 
     pub fn set_position(&mut self, pos: u64) {
@@ -55,23 +113,24 @@ impl<'a> Cursor<&'a mut [u8]> {
     }
 }
 
-impl<'a> Write for Cursor<&'a mut [u8]> {
+impl Write for Cursor<&mut [u8]> {
     fn write(&mut self, data: &[u8]) -> Result<usize> {
-        let amt = (&mut self.0[(self.1 as usize)..]).write(data)?;
+        let start = core::cmp::min(self.1 as usize, self.0.len());
+        let amt = (&mut self.0[start..]).write(data)?;
         self.1 += amt as u64;
 
         Ok(amt)
     }
 }
 
-impl<'a> Seek for Cursor<&'a mut [u8]> {
+impl Seek for Cursor<&mut [u8]> {
     fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
         let (start, offset) = match pos {
             SeekFrom::Start(n) => {
                 self.1 = n;
                 return Ok(n);
             }
-            SeekFrom::Current(n) => (self.1 as u64, n),
+            SeekFrom::Current(n) => (self.1, n),
         };
         let new_pos = if offset >= 0 {
             start.checked_add(offset as u64)
@@ -83,7 +142,133 @@ impl<'a> Seek for Cursor<&'a mut [u8]> {
                 self.1 = n;
                 Ok(n)
             }
-            None => panic!("invalid seek to a negative or overflowing position"),
+            None => Err(Error::InvalidSeek),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_all_short() {
+        let mut buf = [0u8; 4];
+        let mut out = &mut buf[..];
+
+        assert_eq!(out.write_all(b"abcdef"), Err(Error::WriteZero));
+        assert_eq!(&buf, b"abcd");
+    }
+
+    #[test]
+    fn test_cursor_write_past_end() {
+        let mut buf = [0u8; 4];
+        let mut cursor = Cursor::new(&mut buf[..]);
+        cursor.set_position(8);
+
+        assert_eq!(cursor.write(b"ab"), Ok(0));
+        assert_eq!(cursor.write_all(b"ab"), Err(Error::WriteZero));
+    }
+
+    #[test]
+    fn test_gen_error_from_write_all() {
+        use crate::internal::GenError;
+
+        let mut buf = [0u8; 2];
+        let err: GenError = (&mut buf[..]).write_all(b"abc").unwrap_err().into();
+
+        assert!(matches!(err, GenError::IoError(Error::WriteZero)));
+    }
+
+    #[test]
+    fn test_cursor_seek_negative() {
+        let mut buf = [0u8; 4];
+        let mut cursor = Cursor::new(&mut buf[..]);
+        cursor.set_position(2);
+
+        assert_eq!(cursor.seek(SeekFrom::Current(-3)), Err(Error::InvalidSeek));
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(cursor.seek(SeekFrom::Current(-2)), Ok(0));
+    }
+
+    #[test]
+    fn test_gen_error_from_invalid_seek() {
+        use crate::internal::{gen, GenError, WriteContext};
+
+        let mut buf = [0u8; 4];
+        let res = gen(
+            |mut out: WriteContext<Cursor<&mut [u8]>>| {
+                out.seek(SeekFrom::Current(-1))?;
+                Ok(out)
+            },
+            Cursor::new(&mut buf[..]),
+        );
+
+        assert!(matches!(res, Err(GenError::InvalidOffset)));
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn test_heapless_vec() {
+        use crate::bytes::{be_u16, be_u32};
+        use crate::internal::{gen, GenError};
+        use crate::sequence::pair;
+
+        let (vec, pos) = gen(
+            pair(be_u32(0x01020304), be_u16(0x0506)),
+            heapless::Vec::<u8, 16>::new(),
+        )
+        .unwrap();
+        assert_eq!(pos, 6);
+        assert_eq!(&vec[..], &[1, 2, 3, 4, 5, 6][..]);
+
+        let res = gen(
+            pair(be_u32(0x01020304), be_u16(0x0506)),
+            heapless::Vec::<u8, 5>::new(),
+        );
+        assert!(matches!(res, Err(GenError::BufferTooSmall(1))));
+    }
+
+    struct FixedString {
+        buf: [u8; 64],
+        len: usize,
+    }
+
+    impl core::fmt::Write for FixedString {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            let end = self.len + s.len();
+            if end > self.buf.len() {
+                return Err(core::fmt::Error);
+            }
+            self.buf[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
         }
     }
+
+    #[test]
+    fn test_gen_error_display() {
+        use crate::internal::GenError;
+        use core::fmt::Write as _;
+
+        let mut buf = [0u8; 2];
+        let mut out = FixedString {
+            buf: [0u8; 64],
+            len: 0,
+        };
+
+        let err: GenError = (&mut buf[..]).write_all(b"abc").unwrap_err().into();
+        write!(out, "{}", err).unwrap();
+        assert_eq!(
+            &out.buf[..out.len],
+            b"I/O error: failed to write whole buffer"
+        );
+
+        let mut out = FixedString {
+            buf: [0u8; 64],
+            len: 0,
+        };
+        write!(out, "{}", Error::WriteZero).unwrap();
+        assert_eq!(&out.buf[..out.len], b"failed to write whole buffer");
+    }
 }
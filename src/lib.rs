@@ -19,14 +19,12 @@ pub mod lib {
         pub mod io {
             pub use std::io::{Cursor, Error, Result, Seek, SeekFrom, Write};
         }
-        pub use std::{cmp, fmt, iter, mem, result, slice};
+        pub use std::{cell, cmp, fmt, iter, mem, result, slice};
     }
 
     #[cfg(not(feature = "std"))]
     pub mod std {
-        pub use core::{cmp, iter, mem, result, slice};
-        #[macro_use]
-        pub use core::fmt;
+        pub use core::{cell, cmp, fmt, iter, mem, result, slice};
 
         pub mod io {
             pub use crate::io_compat::*;
@@ -1,5 +1,7 @@
 //! serializers working on a list of elements (vectors, iterators, etc)
-use crate::internal::{SerializeFn, WriteContext};
+use crate::combinator::slice;
+use crate::internal::{GenError, GenResult, Reserve, SerializeFn, WriteContext};
+use crate::lib::std::cell::Cell;
 use crate::lib::std::io::Write;
 
 /// Applies an iterator of serializers of the same type
@@ -34,6 +36,129 @@ where
     }
 }
 
+/// Applies an iterator of serializers of the same type, reporting which one failed
+///
+/// If a serializer fails, the error is replaced with `GenError::CustomError` holding the index
+/// of that serializer in the iterator. Use `all` to get the original error instead.
+///
+/// ```rust
+/// use cookie_factory::{gen, GenError, multi::all_indexed, combinator::string};
+///
+/// let mut buf = [0u8; 10];
+///
+/// let data = vec!["abcd", "efgh", "ijkl"];
+/// match gen(all_indexed(data.iter().map(string)), &mut buf[..]) {
+///   Err(GenError::CustomError(index)) => assert_eq!(index, 2),
+///   _ => panic!("the third element should not fit"),
+/// }
+/// ```
+pub fn all_indexed<G, W: Write, It>(values: It) -> impl SerializeFn<W>
+where
+    G: SerializeFn<W>,
+    It: Clone + Iterator<Item = G>,
+{
+    move |mut out: WriteContext<W>| {
+        let it = values.clone();
+
+        for (i, v) in it.enumerate() {
+            out = v(out).map_err(|_| GenError::CustomError(i as u32))?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Applies serializers from an iterator until one of them fails, and returns the result along
+/// with the number of serializers fully applied
+///
+/// Each serializer is run once, directly on the output. When the output runs out of space with
+/// `GenError::BufferTooSmall`, the count tells how many elements were written before it and
+/// which ones were dropped. The output is consumed by the failing serializer, which may have
+/// written part of its element after the last complete one.
+///
+/// ```rust
+/// use cookie_factory::{GenError, WriteContext, multi::all_tolerant, combinator::string};
+///
+/// let mut buf = [0u8; 10];
+///
+/// let data = vec!["abcd", "efgh", "ijkl"];
+/// {
+///   let (res, count) = all_tolerant(data.iter().map(string))(WriteContext::from(&mut buf[..]));
+///   assert_eq!(count, 2);
+///   assert!(matches!(res, Err(GenError::BufferTooSmall(2))));
+/// }
+///
+/// assert_eq!(&buf[..8], &b"abcdefgh"[..]);
+/// ```
+pub fn all_tolerant<G, W: Write, It>(
+    values: It,
+) -> impl Fn(WriteContext<W>) -> (GenResult<W>, usize)
+where
+    G: SerializeFn<W>,
+    It: Clone + Iterator<Item = G>,
+{
+    move |mut out: WriteContext<W>| {
+        let it = values.clone();
+        let mut count = 0;
+
+        for v in it {
+            match v(out) {
+                Ok(o) => out = o,
+                Err(e) => return (Err(e), count),
+            }
+            count += 1;
+        }
+
+        (Ok(out), count)
+    }
+}
+
+/// Applies an iterator of serializers of the same type, whose length is known in advance,
+/// reserving room for the whole output in growable outputs
+///
+/// The first serializer is applied, then the output reserves room for the remaining ones,
+/// assuming they all write as many bytes as the first. This fits collections of fixed size
+/// elements: in the `multi` benchmark, serializing 10k `be_u32` into a `Vec` reallocates once
+/// instead of once per capacity doubling, and runs about three times faster than `all`. For
+/// fixed size outputs, this behaves like `all`.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::all_exact, bytes::be_u16};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let data = vec![1u16, 2, 3];
+/// {
+///   let (buf, pos) = gen(all_exact(data.iter().map(|v| be_u16(*v))), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 6);
+///   assert_eq!(buf.len(), 100 - 6);
+/// }
+///
+/// assert_eq!(&buf[..6], &[0u8, 1, 0, 2, 0, 3][..]);
+/// ```
+pub fn all_exact<G, W: Reserve, It>(values: It) -> impl SerializeFn<W>
+where
+    G: SerializeFn<W>,
+    It: Clone + ExactSizeIterator<Item = G>,
+{
+    move |mut out: WriteContext<W>| {
+        let mut it = values.clone();
+
+        if let Some(first) = it.next() {
+            let start = out.position;
+            out = first(out)?;
+            let element = (out.position - start) as usize;
+            out.write.reserve(element.saturating_mul(it.len()));
+        }
+
+        for v in it {
+            out = v(out)?;
+        }
+
+        Ok(out)
+    }
+}
+
 /// Applies an iterator of serializers of the same type with a separator between each serializer
 ///
 /// ```rust
@@ -74,6 +199,208 @@ where
     }
 }
 
+/// Applies an iterator of serializers of the same type, consuming the iterator
+///
+/// Unlike `all`, the iterator does not need to be `Clone`, but the serializer can only be run
+/// once: running it again returns `GenError::InvalidValue` without writing anything.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::all_once, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let data = vec![String::from("abcd"), String::from("efgh")];
+/// {
+///   let (buf, pos) = gen(all_once(data.into_iter().map(string)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 8);
+///   assert_eq!(buf.len(), 100 - 8);
+/// }
+///
+/// assert_eq!(&buf[..8], &b"abcdefgh"[..]);
+/// ```
+pub fn all_once<G, W: Write, It>(values: It) -> impl SerializeFn<W>
+where
+    G: SerializeFn<W>,
+    It: Iterator<Item = G>,
+{
+    let values = Cell::new(Some(values));
+
+    move |mut out: WriteContext<W>| {
+        let it = values.take().ok_or(GenError::InvalidValue)?;
+
+        for v in it {
+            out = v(out)?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Applies an iterator of serializers of the same type with a separator between each serializer,
+/// consuming the iterator
+///
+/// Unlike `separated_list`, the iterator does not need to be `Clone`, but the serializer can
+/// only be run once: running it again returns `GenError::InvalidValue` without writing anything.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::separated_list_once, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let data = vec![String::from("abcd"), String::from("efgh")];
+/// {
+///   let (buf, pos) = gen(separated_list_once(string(","), data.into_iter().map(string)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 9);
+///   assert_eq!(buf.len(), 100 - 9);
+/// }
+///
+/// assert_eq!(&buf[..9], &b"abcd,efgh"[..]);
+/// ```
+pub fn separated_list_once<F, G, W: Write, It>(sep: F, values: It) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+    G: SerializeFn<W>,
+    It: Iterator<Item = G>,
+{
+    let values = Cell::new(Some(values));
+
+    move |mut out: WriteContext<W>| {
+        let mut it = values.take().ok_or(GenError::InvalidValue)?;
+
+        match it.next() {
+            None => return Ok(out),
+            Some(first) => {
+                out = first(out)?;
+            }
+        }
+
+        for v in it {
+            out = sep(out).and_then(v)?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Applies an iterator of serializers of the same type, with a separator built from the index
+/// of the gap between each serializer
+///
+/// The first gap, between the first and second serializers, has index 0.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::separated_list_by, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let data = vec!["a", "b", "c", "d", "e", "f", "g"];
+/// {
+///   let sep = |i: usize| string(if i % 3 == 2 { "\n" } else { ";" });
+///   let (buf, pos) = gen(separated_list_by(sep, data.iter().map(string)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 13);
+///   assert_eq!(buf.len(), 100 - 13);
+/// }
+///
+/// assert_eq!(&buf[..13], &b"a;b;c\nd;e;f\ng"[..]);
+/// ```
+pub fn separated_list_by<SF, S, G, W: Write, It>(sep: SF, values: It) -> impl SerializeFn<W>
+where
+    SF: Fn(usize) -> S,
+    S: SerializeFn<W>,
+    G: SerializeFn<W>,
+    It: Clone + Iterator<Item = G>,
+{
+    move |mut out: WriteContext<W>| {
+        let mut it = values.clone();
+
+        match it.next() {
+            None => return Ok(out),
+            Some(first) => {
+                out = first(out)?;
+            }
+        }
+
+        for (i, v) in it.enumerate() {
+            out = sep(i)(out).and_then(v)?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Applies an iterator of serializers of the same type, with a terminator after each serializer
+///
+/// Unlike `separated_list`, the terminator is also written after the last serializer.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::terminated_list, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let data = vec!["abcd", "efgh", "ijkl"];
+/// {
+///   let (buf, pos) = gen(terminated_list(string(","), data.iter().map(string)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 15);
+///   assert_eq!(buf.len(), 100 - 15);
+/// }
+///
+/// assert_eq!(&buf[..15], &b"abcd,efgh,ijkl,"[..]);
+/// ```
+pub fn terminated_list<F, G, W: Write, It>(term: F, values: It) -> impl SerializeFn<W>
+where
+    F: SerializeFn<W>,
+    G: SerializeFn<W>,
+    It: Clone + Iterator<Item = G>,
+{
+    move |mut out: WriteContext<W>| {
+        let it = values.clone();
+
+        for v in it {
+            out = v(out).and_then(&term)?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Applies a generator over pairs of items taken from two iterators, and applies the serializers
+/// generated
+///
+/// The iterators are zipped, so serialization stops at the end of the shorter one.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::zip_with, sequence::pair, bytes::be_u8, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let keys = [1u8, 2];
+/// let values = ["a", "b"];
+/// {
+///   let (buf, pos) = gen(
+///     zip_with(keys.iter(), values.iter(), |k, v| pair(be_u8(*k), string(v))),
+///     &mut buf[..],
+///   ).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &[1u8, b'a', 2, b'b'][..]);
+/// ```
+pub fn zip_with<A, B, F, G, W: Write, ItA, ItB>(a: ItA, b: ItB, f: F) -> impl SerializeFn<W>
+where
+    F: Fn(A, B) -> G,
+    G: SerializeFn<W>,
+    ItA: Clone + Iterator<Item = A>,
+    ItB: Clone + Iterator<Item = B>,
+{
+    move |mut out: WriteContext<W>| {
+        for (x, y) in a.clone().zip(b.clone()) {
+            out = f(x, y)(out)?;
+        }
+
+        Ok(out)
+    }
+}
+
 /// Applies a generator over an iterator of values, and applies the serializers generated
 ///
 /// ```rust
@@ -105,3 +432,300 @@ where
         Ok(out)
     }
 }
+
+/// Applies a generator over an iterator of values, and applies the serializers generated,
+/// returning the output along with the number of values serialized
+///
+/// This fits the `Gen` argument of `back_to_the_buffer`, to write the count before the values.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::count_items, combinator::{back_to_the_buffer, string}, bytes::be_u8};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let data = vec!["abcd", "efgh", "ijkl"];
+/// {
+///   let (buf, pos) = gen(
+///     back_to_the_buffer(1, count_items(&data, string), |out, count| be_u8(count as u8)(out)),
+///     &mut buf[..],
+///   ).unwrap();
+///   assert_eq!(pos, 13);
+///   assert_eq!(buf.len(), 100 - 13);
+/// }
+///
+/// assert_eq!(&buf[..13], &b"\x03abcdefghijkl"[..]);
+/// ```
+pub fn count_items<E, It, I, F, G, W: Write>(
+    items: I,
+    generator: F,
+) -> impl Fn(WriteContext<W>) -> Result<(WriteContext<W>, usize), GenError>
+where
+    It: Iterator<Item = E> + Clone,
+    I: IntoIterator<Item = E, IntoIter = It>,
+    F: Fn(E) -> G,
+    G: SerializeFn<W>,
+{
+    let items = items.into_iter();
+    move |mut out: WriteContext<W>| {
+        let mut count = 0;
+        for item in items.clone() {
+            out = generator(item)(out)?;
+            count += 1;
+        }
+        Ok((out, count))
+    }
+}
+
+/// Applies the serializers generated by `f` for each index from `0` to `n - 1`
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::count, bytes::be_u8};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(count(4, |i| be_u8(i as u8)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &[0u8, 1, 2, 3][..]);
+/// ```
+pub fn count<F, G, W: Write>(n: usize, f: F) -> impl SerializeFn<W>
+where
+    F: Fn(usize) -> G,
+    G: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        for i in 0..n {
+            out = f(i)(out)?;
+        }
+        Ok(out)
+    }
+}
+
+/// Splits a byte slice into fragments of at most `max_payload` bytes, each preceded by a header
+///
+/// `header` is called with the length of the fragment and a flag indicating whether more
+/// fragments follow. An empty input still produces a single, empty, final fragment.
+///
+/// Returns `GenError::InvalidValue` if `max_payload` is 0.
+///
+/// ```rust
+/// use cookie_factory::{gen, multi::fragmented, sequence::pair, bytes::be_u8};
+///
+/// let mut buf = [0u8; 100];
+///
+/// {
+///   let (buf, pos) = gen(
+///     fragmented(&b"abcde"[..], 2, |len, more| pair(be_u8(more as u8), be_u8(len as u8))),
+///     &mut buf[..]
+///   ).unwrap();
+///   assert_eq!(pos, 11);
+///   assert_eq!(buf.len(), 100 - 11);
+/// }
+///
+/// assert_eq!(&buf[..11], &b"\x01\x02ab\x01\x02cd\x00\x01e"[..]);
+/// ```
+pub fn fragmented<S, H, HF, W: Write>(
+    data: S,
+    max_payload: usize,
+    header: HF,
+) -> impl SerializeFn<W>
+where
+    S: AsRef<[u8]>,
+    HF: Fn(usize, bool) -> H,
+    H: SerializeFn<W>,
+{
+    move |mut out: WriteContext<W>| {
+        if max_payload == 0 {
+            return Err(GenError::InvalidValue);
+        }
+
+        let mut chunks = data.as_ref().chunks(max_payload).peekable();
+
+        if chunks.peek().is_none() {
+            return header(0, false)(out);
+        }
+
+        while let Some(chunk) = chunks.next() {
+            let more = chunks.peek().is_some();
+            out = header(chunk.len(), more)(out)?;
+            out = slice(chunk)(out)?;
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bytes::{be_u16, be_u8};
+    use crate::internal::{gen, limit};
+    use crate::sequence::pair;
+
+    #[test]
+    fn test_once() {
+        // deliberately not `Clone`
+        struct Countdown(u8);
+
+        impl Iterator for Countdown {
+            type Item = u8;
+
+            fn next(&mut self) -> Option<u8> {
+                self.0 = self.0.checked_sub(1)?;
+                Some(self.0)
+            }
+        }
+
+        let mut buf = [0u8; 10];
+        {
+            let serializer = all_once(Countdown(3).map(be_u8));
+            let (rest, pos) = gen(&serializer, &mut buf[..]).unwrap();
+            assert_eq!(pos, 3);
+            assert!(matches!(
+                gen(&serializer, rest),
+                Err(GenError::InvalidValue)
+            ));
+        }
+        assert_eq!(&buf[..3], &[2, 1, 0]);
+
+        let mut buf = [0u8; 10];
+        {
+            let serializer = separated_list_once(be_u8(0xff), Countdown(3).map(be_u8));
+            let (_, pos) = gen(serializer, &mut buf[..]).unwrap();
+            assert_eq!(pos, 5);
+        }
+        assert_eq!(&buf[..5], &[2, 0xff, 1, 0xff, 0]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_count_items() {
+        use crate::combinator::back_to_the_buffer;
+
+        let values = [1u16, 2, 3];
+        let buf = crate::gen_simple(
+            back_to_the_buffer(2, count_items(&values, |v| be_u16(*v)), |out, count| {
+                be_u16(count as u16)(out)
+            }),
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(&buf[..], &[0, 3, 0, 1, 0, 2, 0, 3]);
+
+        let empty: [u16; 0] = [];
+        let mut buf = [0u8; 2];
+        let (_, count) =
+            count_items(&empty, |v| be_u16(*v))(WriteContext::from(&mut buf[..])).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_all_indexed() {
+        use crate::bytes::be_u32;
+
+        let mut buf = [0u8; 10];
+        let values = [1u32, 2, 3, 4];
+
+        match gen(all_indexed(values.iter().map(|v| be_u32(*v))), &mut buf[..]) {
+            Err(GenError::CustomError(2)) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+
+        let mut buf = [0u8; 16];
+        let (_, pos) = gen(all_indexed(values.iter().map(|v| be_u32(*v))), &mut buf[..]).unwrap();
+        assert_eq!(pos, 16);
+    }
+
+    #[test]
+    fn test_all_tolerant() {
+        use crate::combinator::string;
+
+        let mut buf = [0u8; 10];
+        let words = ["abcd", "efgh", "ijkl"];
+
+        {
+            let (res, count) =
+                all_tolerant(words.iter().map(string))(WriteContext::from(&mut buf[..]));
+            assert_eq!(count, 2);
+            match res {
+                Err(GenError::BufferTooSmall(2)) => {}
+                Err(e) => panic!("unexpected error: {:?}", e),
+                Ok(_) => panic!("should have failed"),
+            }
+        }
+        assert_eq!(&buf[..8], b"abcdefgh");
+
+        // writers that do not know their remaining capacity
+        let mut buf = [0u8; 100];
+        let (res, count) =
+            all_tolerant(words.iter().map(string))(WriteContext::from(limit(&mut buf[..], 10)));
+        assert_eq!(count, 2);
+        assert!(matches!(res, Err(GenError::BufferTooSmall(2))));
+
+        let mut buf = [0u8; 12];
+        let (res, count) = all_tolerant(words.iter().map(string))(WriteContext::from(&mut buf[..]));
+        assert_eq!(count, 3);
+        assert_eq!(res.unwrap().position, 12);
+    }
+
+    #[test]
+    fn test_fragmented() {
+        let data = [0x42u8; 250];
+        let mut buf = [0u8; 259];
+
+        {
+            let (rest, pos) = gen(
+                fragmented(&data[..], 100, |len, more| {
+                    pair(be_u8(more as u8), be_u16(len as u16))
+                }),
+                &mut buf[..],
+            )
+            .unwrap();
+            assert_eq!(pos, 259);
+            assert!(rest.is_empty());
+        }
+
+        assert_eq!(&buf[0..3], &[1, 0, 100]);
+        assert_eq!(&buf[3..103], &data[..100]);
+        assert_eq!(&buf[103..106], &[1, 0, 100]);
+        assert_eq!(&buf[106..206], &data[..100]);
+        assert_eq!(&buf[206..209], &[0, 0, 50]);
+        assert_eq!(&buf[209..259], &data[..50]);
+    }
+
+    #[test]
+    fn test_fragmented_empty() {
+        let mut buf = [0u8; 3];
+
+        let (_, pos) = gen(
+            fragmented(&b""[..], 100, |len, more| {
+                pair(be_u8(more as u8), be_u16(len as u16))
+            }),
+            &mut buf[..],
+        )
+        .unwrap();
+
+        assert_eq!(pos, 3);
+        assert_eq!(&buf, &[0, 0, 0]);
+    }
+
+    #[test]
+    fn test_fragmented_zero_payload() {
+        let mut buf = [0u8; 16];
+
+        match gen(
+            fragmented(&b"abc"[..], 0, |len, more| {
+                pair(be_u8(more as u8), be_u16(len as u16))
+            }),
+            &mut buf[..],
+        ) {
+            Err(GenError::InvalidValue) => {}
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("should have failed"),
+        }
+    }
+}
@@ -25,6 +25,42 @@ where
     move |out: WriteContext<W>| first(out).and_then(&second)
 }
 
+/// Extension methods to chain serializers fluently
+///
+/// This trait is implemented for all serializers.
+///
+/// ```rust
+/// use cookie_factory::{gen, sequence::SerializeFnExt, combinator::string};
+///
+/// let mut buf = [0u8; 100];
+///
+/// let rest = vec!["c", "d"];
+/// {
+///   let (buf, pos) = gen(string("a").then(string("b")).and_all(rest.iter().map(string)), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 4);
+///   assert_eq!(buf.len(), 100 - 4);
+/// }
+///
+/// assert_eq!(&buf[..4], &b"abcd"[..]);
+/// ```
+pub trait SerializeFnExt<W: Write>: SerializeFn<W> + Sized {
+    /// Applies `next` after this serializer, like `pair`
+    fn then<G: SerializeFn<W>>(self, next: G) -> impl SerializeFn<W> {
+        pair(self, next)
+    }
+
+    /// Applies the serializers from `rest` after this serializer, like `multi::all`
+    fn and_all<G, It>(self, rest: It) -> impl SerializeFn<W>
+    where
+        G: SerializeFn<W>,
+        It: Clone + Iterator<Item = G>,
+    {
+        pair(self, crate::multi::all(rest))
+    }
+}
+
+impl<W: Write, F: SerializeFn<W>> SerializeFnExt<W> for F {}
+
 /// Helper trait for the `tuple` combinator
 pub trait Tuple<W> {
     fn serialize(&self, w: WriteContext<W>) -> GenResult<W>;
@@ -91,7 +127,7 @@ macro_rules! tuple_trait_inner(
 // Takes an integer and a macro invocation, and changes the macro invocation
 // to take the incremented integer as the first argument
 //
-// Works for integers between 0 and 19.
+// Works for integers between 0 and 30.
 #[doc(hidden)]
 macro_rules! succ (
   (0, $submac:ident ! ($($rest:tt)*)) => ($submac!(1, $($rest)*));
@@ -114,16 +150,38 @@ macro_rules! succ (
   (17, $submac:ident ! ($($rest:tt)*)) => ($submac!(18, $($rest)*));
   (18, $submac:ident ! ($($rest:tt)*)) => ($submac!(19, $($rest)*));
   (19, $submac:ident ! ($($rest:tt)*)) => ($submac!(20, $($rest)*));
+  (20, $submac:ident ! ($($rest:tt)*)) => ($submac!(21, $($rest)*));
+  (21, $submac:ident ! ($($rest:tt)*)) => ($submac!(22, $($rest)*));
+  (22, $submac:ident ! ($($rest:tt)*)) => ($submac!(23, $($rest)*));
+  (23, $submac:ident ! ($($rest:tt)*)) => ($submac!(24, $($rest)*));
+  (24, $submac:ident ! ($($rest:tt)*)) => ($submac!(25, $($rest)*));
+  (25, $submac:ident ! ($($rest:tt)*)) => ($submac!(26, $($rest)*));
+  (26, $submac:ident ! ($($rest:tt)*)) => ($submac!(27, $($rest)*));
+  (27, $submac:ident ! ($($rest:tt)*)) => ($submac!(28, $($rest)*));
+  (28, $submac:ident ! ($($rest:tt)*)) => ($submac!(29, $($rest)*));
+  (29, $submac:ident ! ($($rest:tt)*)) => ($submac!(30, $($rest)*));
+  (30, $submac:ident ! ($($rest:tt)*)) => ($submac!(31, $($rest)*));
 );
 
 tuple_trait!(
     FnA, FnB, FnC, FnD, FnE, FnF, FnG, FnH, FnI, FnJ, FnK, FnL, FnM, FnN, FnO, FnP, FnQ, FnR, FnS,
-    FnT, FnU
+    FnT, FnU, FnV, FnW, FnX, FnY, FnZ, FnAA, FnAB, FnAC, FnAD, FnAE, FnAF
 );
 
+impl<W: Write, F: SerializeFn<W>, const N: usize> Tuple<W> for [F; N] {
+    fn serialize(&self, mut w: WriteContext<W>) -> GenResult<W> {
+        for f in self.iter() {
+            w = f(w)?;
+        }
+
+        Ok(w)
+    }
+}
+
 /// Applies multiple serializers in sequence
 ///
-/// Currently tuples up to 20 elements are supported.
+/// Currently tuples up to 32 elements are supported, as well as arrays of serializers of the
+/// same type.
 ///
 /// ```rust
 /// use cookie_factory::{gen, sequence::tuple, combinator::string, bytes::be_u16};
@@ -144,6 +202,14 @@ tuple_trait!(
 /// }
 ///
 /// assert_eq!(&buf[..10], &b"abcd\x00\x20efgh"[..]);
+///
+/// {
+///   let (buf, pos) = gen(tuple([be_u16(1), be_u16(2), be_u16(3)]), &mut buf[..]).unwrap();
+///   assert_eq!(pos, 6);
+///   assert_eq!(buf.len(), 100 - 6);
+/// }
+///
+/// assert_eq!(&buf[..6], &b"\x00\x01\x00\x02\x00\x03"[..]);
 /// ```
 pub fn tuple<W: Write, List: Tuple<W>>(l: List) -> impl SerializeFn<W> {
     move |w: WriteContext<W>| l.serialize(w)
@@ -188,4 +254,120 @@ mod test {
 
         assert_eq!(&buf[..], b"123456780123");
     }
+
+    #[test]
+    fn test_serializer_ref() {
+        use crate::bytes::be_u16;
+        use crate::internal::{gen, gen_ref};
+        use crate::multi::all;
+
+        let mut buf1 = [0u8; 8];
+        let mut buf2 = [0u8; 8];
+        let mut buf3 = [0u8; 4];
+
+        {
+            let serializer = pair(string("ab"), be_u16(0x0102));
+
+            let (_, pos1) = gen(tuple((&serializer, &serializer)), &mut buf1[..]).unwrap();
+            assert_eq!(pos1, 8);
+
+            let (_, pos2) = gen(
+                all([&serializer, &serializer].iter().copied()),
+                &mut buf2[..],
+            )
+            .unwrap();
+            assert_eq!(pos2, 8);
+
+            let (_, pos3) = gen_ref(&serializer, &mut buf3[..]).unwrap();
+            assert_eq!(pos3, 4);
+        }
+
+        assert_eq!(&buf1, b"ab\x01\x02ab\x01\x02");
+        assert_eq!(&buf2, b"ab\x01\x02ab\x01\x02");
+        assert_eq!(&buf3, b"ab\x01\x02");
+    }
+
+    #[test]
+    fn test_dyn_write() {
+        use crate::bytes::be_u8;
+        use crate::internal::gen;
+        use crate::lib::std::io::Write;
+
+        let mut buf = [0u8; 4];
+
+        {
+            let mut target = &mut buf[..];
+            let out: &mut dyn Write = &mut target;
+            let (_, pos) = gen(tuple((string("a"), be_u8(1))), out).unwrap();
+            assert_eq!(pos, 2);
+        }
+
+        assert_eq!(&buf[..2], &b"a\x01"[..]);
+    }
+
+    #[test]
+    fn test_tuple_26() {
+        use crate::bytes::be_u8;
+        use crate::internal::gen;
+
+        let mut buf = [0u8; 26];
+
+        let (_, pos) = gen(
+            tuple((
+                be_u8(b'a'),
+                be_u8(b'b'),
+                be_u8(b'c'),
+                be_u8(b'd'),
+                be_u8(b'e'),
+                be_u8(b'f'),
+                be_u8(b'g'),
+                be_u8(b'h'),
+                be_u8(b'i'),
+                be_u8(b'j'),
+                be_u8(b'k'),
+                be_u8(b'l'),
+                be_u8(b'm'),
+                be_u8(b'n'),
+                be_u8(b'o'),
+                be_u8(b'p'),
+                be_u8(b'q'),
+                be_u8(b'r'),
+                be_u8(b's'),
+                be_u8(b't'),
+                be_u8(b'u'),
+                be_u8(b'v'),
+                be_u8(b'w'),
+                be_u8(b'x'),
+                be_u8(b'y'),
+                string("z"),
+            )),
+            &mut buf[..],
+        )
+        .unwrap();
+
+        assert_eq!(pos, 26);
+        assert_eq!(&buf[..], b"abcdefghijklmnopqrstuvwxyz");
+    }
+
+    #[test]
+    fn test_tuple_array() {
+        use crate::bytes::be_u8;
+        use crate::internal::gen;
+        use crate::multi::all;
+
+        let mut buf = [0u8; 4];
+        let mut expected = [0u8; 4];
+
+        let (_, pos) = gen(tuple([be_u8(1), be_u8(2), be_u8(3)]), &mut buf[..]).unwrap();
+        let (_, expected_pos) =
+            gen(all([1, 2, 3].iter().map(|i| be_u8(*i))), &mut expected[..]).unwrap();
+
+        assert_eq!(pos, 3);
+        assert_eq!(pos, expected_pos);
+        assert_eq!(buf, expected);
+
+        let mut small = [0u8; 2];
+        assert!(gen(tuple([be_u8(1), be_u8(2), be_u8(3)]), &mut small[..]).is_err());
+        assert_eq!(small, [1, 2]);
+    }
 }
@@ -0,0 +1,105 @@
+extern crate cookie_factory;
+
+use cookie_factory::async_bufwriter::{from_sync, gen, gen_async, AsyncBufWriter};
+use cookie_factory::bytes::{be_u16, be_u32};
+use cookie_factory::combinator::{back_to_the_buffer, string};
+use cookie_factory::multi::all;
+use cookie_factory::sequence::pair;
+use cookie_factory::sequence::tuple;
+use futures_io::AsyncWrite;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// `AsyncWrite` accepting at most 8 bytes per call, and returning `Pending`
+/// every other time it is polled
+#[derive(Default)]
+struct SlowWriter {
+    data: Vec<u8>,
+    ready: bool,
+    writes: usize,
+}
+
+impl AsyncWrite for SlowWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.ready {
+            self.ready = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        self.ready = false;
+        self.writes += 1;
+        let sz = buf.len().min(8);
+        self.data.extend_from_slice(&buf[..sz]);
+        Poll::Ready(Ok(sz))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[async_std::test]
+async fn chunked_async_writes() {
+    let values = [1u32, 2, 3, 4, 5, 6, 7, 8];
+    let serializer = pair(string("header:"), all(values.iter().map(|v| be_u32(*v))));
+
+    let (w, pos) = gen_async(from_sync(serializer), SlowWriter::default())
+        .await
+        .unwrap();
+
+    let mut expected = b"header:".to_vec();
+    for v in values.iter() {
+        expected.extend_from_slice(&v.to_be_bytes());
+    }
+
+    assert_eq!(pos, 39);
+    assert_eq!(w.writes, 5);
+    assert_eq!(w.data, expected);
+}
+
+#[async_std::test]
+async fn length_prefixed_async_bufwriter() {
+    let serializer = tuple((
+        string("msg"),
+        back_to_the_buffer(
+            2,
+            |buf| cookie_factory::gen(string("hello"), buf),
+            |buf, len| cookie_factory::gen_simple(be_u16(len as u16), buf),
+        ),
+        string("!"),
+    ));
+
+    let (w, sz) = gen(serializer, AsyncBufWriter::with_capacity(32, Vec::new()))
+        .await
+        .unwrap();
+    let (inner, remaining) = w.into_parts();
+
+    assert_eq!(sz, 11);
+    assert!(remaining.is_empty());
+    assert_eq!(&inner[..], &b"msg\x00\x05hello!"[..]);
+}
+
+#[async_std::test]
+async fn back_to_the_buffer_async_bufwriter_too_small() {
+    let serializer = back_to_the_buffer(
+        4,
+        |buf| cookie_factory::gen(string("hello"), buf),
+        |buf, len| cookie_factory::gen_simple(be_u32(len as u32), buf),
+    );
+
+    assert!(
+        gen(serializer, AsyncBufWriter::with_capacity(3, Vec::new()))
+            .await
+            .is_err()
+    );
+}
@@ -7,7 +7,8 @@ use crate::implementation::*;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cookie_factory::gen_simple;
+    use cookie_factory::combinator::{stream, Stream};
+    use cookie_factory::{gen, gen_simple};
     use std::io::Cursor;
     use std::str::from_utf8;
 
@@ -68,38 +69,58 @@ mod tests {
         );
     }
 
-    /*
     #[test]
     fn chunked_http() {
-      let mut mem: [u8; 1024] = [0; 1024];
-      let s = &mut mem[..];
+        let mut mem: [u8; 1024] = [0; 1024];
+
+        let request = RequestHeaders {
+            method: "GET",
+            uri: "/hello/test/a/b/c?name=value#hash",
+            headers: [
+                Header {
+                    name: "Host",
+                    value: "lolcatho.st",
+                },
+                Header {
+                    name: "Transfer-Encoding",
+                    value: "chunked",
+                },
+            ]
+            .to_vec(),
+        };
 
-      let request = RequestHeaders {
-        method: "GET",
-        uri: "/hello/test/a/b/c?name=value#hash",
-        headers: [
-          Header { name: "Host", value: "lolcatho.st" },
-          Header { name: "User-agent", value: "cookie-factory" },
-          Header { name: "Content-Length", value: "13" },
-          Header { name: "Connection", value: "Close" },
-        ].iter().cloned().collect(),
-      };
+        let index = {
+            let chunks = Stream::new();
+            let (s, headers_len) = gen(fn_request_headers(&request), &mut mem[..]).unwrap();
+            assert_eq!(headers_len, 97);
 
-      let mut sr = chunked_request(&request);
-      assert_eq!(sr.serialize(&mut s[..132]), Ok((132, Serialized::Continue)));
+            // nothing queued yet
+            let (s, len) = gen(stream(&chunks), s).unwrap();
+            assert_eq!(len, 0);
 
-      // add chunk
-      sr.second.push(chunk(&b"Hello "[..]));
-      assert_eq!(sr.serialize(&mut s[132..145]), Ok((13, Serialized::Continue)));
-      assert_eq!(from_utf8(&s[132..145]).unwrap(), "\r\n\r\n6\r\nHello ");
+            // add chunk
+            chunks.push(fn_chunk(&b"Hello "[..]));
+            let (s, first_len) = gen(stream(&chunks), s).unwrap();
+            assert_eq!(first_len, 11);
+            assert!(chunks.is_empty());
 
-      // add chunk
-      sr.second.push(chunk(&b"world !"[..]));
-      // add last chunk
-      sr.second.push(chunk(&[]));
+            // add chunk
+            chunks.push(fn_chunk(&b"world !"[..]));
+            // add last chunk
+            chunks.push(fn_chunk(&[]));
+            let (_, second_len) = gen(stream(&chunks), s).unwrap();
+            assert_eq!(second_len, 17);
 
-      assert_eq!(sr.serialize(&mut s[145..]), Ok((19, Serialized::Done)));
-      assert_eq!(from_utf8(&s[136..164]).unwrap(), "6\r\nHello \r\n7\r\nworld !\r\n0\r\n\r\n");
+            (headers_len + first_len + second_len) as usize
+        };
 
-    }*/
+        assert_eq!(
+            from_utf8(&mem[..index]).unwrap(),
+            "GET /hello/test/a/b/c?name=value#hash HTTP/1.1\r\n\
+             Host: lolcatho.st\r\n\
+             Transfer-Encoding: chunked\r\n\
+             \r\n\
+             6\r\nHello \r\n7\r\nworld !\r\n0\r\n\r\n"
+        );
+    }
 }
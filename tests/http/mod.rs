@@ -20,6 +20,7 @@ pub struct Header<'a> {
     pub value: &'a str,
 }
 
+#[allow(dead_code)]
 pub fn cf_request<'a, 'b, 'c>(
     i: (&'a mut [u8], usize),
     r: &'c Request<'b>,
@@ -33,6 +34,7 @@ pub fn cf_request<'a, 'b, 'c>(
     )
 }
 
+#[allow(dead_code)]
 pub fn cf_request_line<'a, 'b>(
     i: (&'a mut [u8], usize),
     method: &'b str,
@@ -47,6 +49,7 @@ pub fn cf_request_line<'a, 'b>(
     )
 }
 
+#[allow(dead_code)]
 pub fn cf_header<'a, 'b, 'c>(
     i: (&'a mut [u8], usize),
     h: &'c Header<'b>,
@@ -60,6 +63,7 @@ pub fn cf_header<'a, 'b, 'c>(
     )
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq)]
 pub struct RequestHeaders<'a> {
     pub method: &'a str,
@@ -99,6 +103,7 @@ pub fn fn_header<'a: 'c, 'c, W: Write + 'c>(h: &'a Header) -> impl SerializeFn<W
     ))
 }
 
+#[allow(dead_code)]
 pub fn fn_request_headers<'a: 'c, 'c, 'b: 'a, W: Write + 'c>(
     r: &'b RequestHeaders<'a>,
 ) -> impl SerializeFn<W> + 'c {
@@ -109,12 +114,7 @@ pub fn fn_request_headers<'a: 'c, 'c, 'b: 'a, W: Write + 'c>(
     ))
 }
 
+#[allow(dead_code)]
 pub fn fn_chunk<'a: 'c, 'c, W: Write + 'c>(sl: &'a [u8]) -> impl SerializeFn<W> + 'c {
     tuple((hex(sl.len()), string("\r\n"), slice(sl), string("\r\n")))
 }
-
-/*
-pub fn chunked_request<'a, 'b: 'a, 'c, T: Serializer + 'a>(r: &'b RequestHeaders<'a>) -> Then<impl Serializer + 'a, Stream<T>> {
-  let s: Stream<T> = Stream::new();
-  rw_request_headers(r).then(s)
-}*/
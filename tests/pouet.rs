@@ -1,5 +1,3 @@
-use std::iter::repeat;
-
 fn slice1(out: &mut [u8]) -> Result<&mut [u8], u32> {
     let data = &b"pouet"[..];
     if out.len() < data.len() {
@@ -11,8 +9,8 @@ fn slice1(out: &mut [u8]) -> Result<&mut [u8], u32> {
 }
 
 fn main() {
-    let mut v1 = repeat(0u8).take(4).collect::<Vec<_>>();
-    let mut v2 = repeat(0u8).take(10).collect::<Vec<_>>();
+    let mut v1 = vec![0u8; 4];
+    let mut v2 = vec![0u8; 10];
 
     println!("res1: {:?}", slice1(&mut v1[..]));
     println!("v1: {:?}", v1);